@@ -0,0 +1,69 @@
+use std::hint::black_box;
+use std::time::SystemTime;
+
+use assert_fs::TempDir;
+use criterion::{BatchSize, Criterion, criterion_group, criterion_main};
+use otot::{Database, HistoryGenerator, SqliteDatabase, VisitSource};
+
+const HISTORY_URLS: usize = 20_000;
+const SEED: u64 = 2024;
+
+fn populated_db(urls: usize) -> (TempDir, SqliteDatabase) {
+    let temp_dir = TempDir::new().unwrap();
+    let mut db = SqliteDatabase::open_at(&temp_dir.path().join("bench.db")).unwrap();
+    let visits = HistoryGenerator::new(SEED).visits(urls, SystemTime::now());
+    db.add_visits(&visits, VisitSource::Import).unwrap();
+    (temp_dir, db)
+}
+
+fn patterns(segments: &[&str]) -> Vec<String> {
+    segments.iter().map(|s| s.to_string()).collect()
+}
+
+fn matching(c: &mut Criterion) {
+    let (_temp_dir, db) = populated_db(HISTORY_URLS);
+    let mut group = c.benchmark_group("fuzzy_match");
+
+    for (name, pattern) in [
+        ("hot_domain", patterns(&["gh", "rust"])),
+        ("deep_path", patterns(&["github", "rust", "issues", "pull"])),
+        ("cold_domain", patterns(&["docker", "latest"])),
+        ("no_match", patterns(&["zzz", "qqq"])),
+    ] {
+        group.bench_function(name, |b| {
+            b.iter(|| db.fuzzy_match(black_box(&pattern)).unwrap())
+        });
+        group.bench_function(format!("{}_best", name), |b| {
+            b.iter(|| db.fuzzy_match_limit(black_box(&pattern), 1).unwrap())
+        });
+    }
+
+    group.finish();
+}
+
+fn ranking(c: &mut Criterion) {
+    let (_temp_dir, db) = populated_db(HISTORY_URLS);
+
+    c.bench_function("get_highest_usage_urls", |b| {
+        b.iter(|| db.get_highest_usage_urls(black_box(20)).unwrap())
+    });
+}
+
+fn import(c: &mut Criterion) {
+    let visits = HistoryGenerator::new(SEED).visits(2_000, SystemTime::now());
+
+    c.bench_function("add_visits_2000_urls", |b| {
+        b.iter_batched(
+            || {
+                let temp_dir = TempDir::new().unwrap();
+                let db = SqliteDatabase::open_at(&temp_dir.path().join("bench.db")).unwrap();
+                (temp_dir, db)
+            },
+            |(_temp_dir, mut db)| db.add_visits(&visits, VisitSource::Import).unwrap(),
+            BatchSize::PerIteration,
+        )
+    });
+}
+
+criterion_group!(benches, matching, ranking, import);
+criterion_main!(benches);
@@ -0,0 +1,146 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::config::OtotConfig;
+use crate::permissions::{create_private_dir, private_open_options, restrict_file};
+use crate::sanitize::redact_secrets;
+use crate::unix_now;
+
+/// One line of the audit log, written before a URL is launched.
+#[derive(Debug, Serialize, PartialEq)]
+pub struct AuditEntry<'a> {
+    pub user: String,
+    pub timestamp: i64,
+    /// The otot command that launched the URL, e.g. `open` or `wrap`
+    pub source: &'a str,
+    pub pattern: &'a str,
+    pub url: &'a str,
+    pub reason: &'a str,
+}
+
+pub fn current_user() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "unknown".to_string())
+}
+
+/// Appends `entry` as a JSON line, creating the log (readable by the current user only) if needed.
+pub fn append_audit_entry(path: &Path, entry: &AuditEntry) -> Result<()> {
+    if let Some(parent) = path.parent()
+        && !parent.exists()
+    {
+        create_private_dir(parent)?;
+    }
+
+    let mut file = private_open_options()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open audit log {}", path.display()))?;
+    restrict_file(path)?;
+
+    let mut line = serde_json::to_string(entry)?;
+    line.push('\n');
+    file.write_all(line.as_bytes())
+        .and_then(|()| file.sync_data())
+        .with_context(|| format!("Failed to write audit log {}", path.display()))?;
+
+    Ok(())
+}
+
+/// What `--reason` said and where the audit log lives, for recording launches.
+pub struct AuditContext {
+    pub reason: Option<String>,
+    pub log_path: PathBuf,
+}
+
+impl AuditContext {
+    /// Records that `address` is about to launch `url`, once every check has passed and
+    /// right before it happens, with secrets in either redacted. With auditing enabled, a
+    /// launch without `--reason` or one that can't be logged is refused.
+    pub fn record_launch(
+        &self,
+        config: &OtotConfig,
+        source: &str,
+        address: &str,
+        url: &str,
+    ) -> Result<()> {
+        if !config.audit.enabled {
+            return Ok(());
+        }
+
+        let Some(reason) = self.reason.as_deref().filter(|r| !r.trim().is_empty()) else {
+            anyhow::bail!("Auditing is enabled: pass --reason to say why this URL is being opened");
+        };
+
+        append_audit_entry(
+            &self.log_path,
+            &AuditEntry {
+                user: current_user(),
+                timestamp: unix_now(),
+                source,
+                pattern: &redact_secrets(address),
+                url: &redact_secrets(url),
+                reason,
+            },
+        )
+        .context("Refusing to open without an audit record")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_fs::TempDir;
+
+    fn entry<'a>(url: &'a str, reason: &'a str) -> AuditEntry<'a> {
+        AuditEntry {
+            user: "alice".to_string(),
+            timestamp: 1700000000,
+            source: "open",
+            pattern: "gh/rust",
+            url,
+            reason,
+        }
+    }
+
+    #[test]
+    fn appends_one_json_object_per_line() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("logs").join("audit.jsonl");
+
+        append_audit_entry(
+            &path,
+            &entry("https://github.com/rust-lang/rust", "TICKET-1"),
+        )
+        .unwrap();
+        append_audit_entry(&path, &entry("https://example.com/", "TICKET-2")).unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<serde_json::Value> = contents
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["url"], "https://github.com/rust-lang/rust");
+        assert_eq!(lines[0]["pattern"], "gh/rust");
+        assert_eq!(lines[1]["reason"], "TICKET-2");
+        assert_eq!(lines[1]["user"], "alice");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn audit_log_is_private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("audit.jsonl");
+
+        append_audit_entry(&path, &entry("https://example.com/", "check")).unwrap();
+
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+}
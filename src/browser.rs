@@ -1,18 +1,93 @@
 use anyhow::Result;
-use log::debug;
-use std::time::SystemTime;
-
-use crate::database::Database;
-use crate::url_classify::{InputType, classify_input};
+use log::{debug, info};
+use std::collections::BTreeMap;
+use std::io;
+use std::process::{Command, ExitStatus, Stdio};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+use url::Url;
+
+use crate::database::{Database, VisitSource};
+use crate::dispatch::{Launcher, launcher_for};
+use crate::sanitize::redact_secrets;
+use crate::url_classify::{InputType, classify_input, clean_pasted_address, known_suffix};
 
 pub trait BrowserOpener {
     fn open(&self, url: &str, browser: Option<&str>) -> std::io::Result<()>;
 }
 
-pub struct SystemBrowserOpener;
+/// How long a launched browser or opener has to fail in before it is taken to have started.
+const LAUNCH_GRACE: Duration = Duration::from_secs(2);
+
+/// The browser or opener was found and run, but exited with a failure straight away.
+#[derive(Debug)]
+pub struct LaunchFailed {
+    pub program: String,
+    pub status: ExitStatus,
+}
+
+impl std::fmt::Display for LaunchFailed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} was found but failed to start ({})",
+            self.program, self.status
+        )
+    }
+}
+
+impl std::error::Error for LaunchFailed {}
+
+/// Opens web pages in the browser and hands other URLs to the command in `openers` for
+/// their kind, or to the system, see `launcher_for`.
+pub struct SystemBrowserOpener {
+    openers: BTreeMap<String, String>,
+    verify_launch: bool,
+}
+
+impl SystemBrowserOpener {
+    pub fn new(openers: BTreeMap<String, String>) -> Self {
+        Self {
+            openers,
+            verify_launch: false,
+        }
+    }
+
+    /// Watch each launch for a moment and fail with `LaunchFailed` when the program exits
+    /// unsuccessfully, instead of trusting the launcher.
+    pub fn verify_launch(mut self, verify: bool) -> Self {
+        self.verify_launch = verify;
+        self
+    }
+
+    fn grace(&self) -> Option<Duration> {
+        Some(LAUNCH_GRACE).filter(|_| self.verify_launch)
+    }
+}
+
 impl BrowserOpener for SystemBrowserOpener {
     fn open(&self, url: &str, browser: Option<&str>) -> std::io::Result<()> {
-        open_url(url, browser)
+        let launcher = launcher_for(url, &self.openers).map_err(std::io::Error::other)?;
+        match launcher {
+            Launcher::Browser if self.verify_launch => match browser {
+                Some(b) => launch(open::with_command(url, b), self.grace()),
+                None => launch_first(open::commands(url), self.grace()),
+            },
+            Launcher::Browser => open_url(url, browser),
+            Launcher::System if self.verify_launch => {
+                launch_first(open::commands(url), self.grace())
+            }
+            Launcher::System => {
+                debug!("Opening link with the system handler");
+                open::that(url)
+            }
+            Launcher::Command(words) => {
+                debug!("Opening link with {:?}", words);
+                let mut command = Command::new(&words[0]);
+                command.args(&words[1..]);
+                launch(command, self.grace())
+            }
+        }
     }
 }
 
@@ -29,32 +104,256 @@ pub fn open_url(url: &str, browser: Option<&str>) -> std::io::Result<()> {
     }
 }
 
-pub fn open_address_impl(
-    opener: &dyn BrowserOpener,
-    db: &mut dyn Database,
-    address: &str,
-    preferred_browser: Option<&str>,
-) -> Result<()> {
+/// Starts `command` without waiting for it. With a `grace` period, waits up to that long
+/// for it to fail and reports `LaunchFailed` if it does; one still running by then, or done
+/// successfully, counts as launched.
+fn launch(mut command: Command, grace: Option<Duration>) -> io::Result<()> {
+    let program = command.get_program().to_string_lossy().into_owned();
+    let mut child = command.stdin(Stdio::null()).spawn().map_err(|e| {
+        if e.kind() == io::ErrorKind::NotFound {
+            io::Error::new(e.kind(), format!("{} was not found", program))
+        } else {
+            e
+        }
+    })?;
+    let Some(grace) = grace else {
+        return Ok(());
+    };
+
+    let started = Instant::now();
+    while started.elapsed() < grace {
+        if let Some(status) = child.try_wait()? {
+            debug!("{} exited with {}", program, status);
+            return if status.success() {
+                Ok(())
+            } else {
+                Err(io::Error::other(LaunchFailed { program, status }))
+            };
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+    debug!("{} still running after {:?}", program, grace);
+    Ok(())
+}
+
+/// `launch` for the first of the system's launchers that is installed.
+fn launch_first(commands: Vec<Command>, grace: Option<Duration>) -> io::Result<()> {
+    let mut last_error = None;
+    for command in commands {
+        match launch(command, grace) {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => last_error = Some(e),
+            result => return result,
+        }
+    }
+    Err(last_error.unwrap_or_else(|| io::Error::other("No launcher for this platform")))
+}
+
+/// Resolves an address to the URL `open` would launch, without opening or recording it.
+pub fn resolve_address(db: &dyn Database, address: &str) -> Result<Option<String>> {
     if address.is_empty() {
         anyhow::bail!("provided address must be a non-empty string");
     }
 
     match classify_input(address) {
         InputType::FullUrl(url) => {
-            db.add_visit(url.as_str(), SystemTime::now())?;
-            opener.open(url.as_str(), preferred_browser)?;
-            Ok(())
+            info!("Resolved {:?} as a URL", address);
+            Ok(Some(url.to_string()))
         }
-        InputType::FuzzyPattern(segments) => match db.get_best_match(&segments)? {
-            Some(best_match) => {
-                db.add_visit(&best_match, SystemTime::now())?;
-                opener.open(best_match.as_str(), preferred_browser)?;
-                Ok(())
+        InputType::LocalPath(path) => {
+            info!("Resolved {:?} as the local path {:?}", address, path);
+            let url = Url::from_file_path(&path).map_err(|()| {
+                anyhow::anyhow!("{} can't be opened as a file URL", path.display())
+            })?;
+            Ok(Some(url.to_string()))
+        }
+        InputType::FuzzyPattern(segments) => {
+            let best = db.get_best_match(&segments)?;
+            info!(
+                "Resolved {:?} as history pattern {:?}: {:?}",
+                address, segments, best
+            );
+            Ok(best)
+        }
+    }
+}
+
+/// Exit status of `open` when it refuses to guess between tied matches.
+pub const AMBIGUOUS_EXIT_CODE: i32 = 3;
+
+/// Several URLs match about equally well and the caller asked not to guess.
+#[derive(Debug)]
+pub struct AmbiguousMatch {
+    pub address: String,
+    pub candidates: Vec<String>,
+}
+
+impl std::fmt::Display for AmbiguousMatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "'{}' matches several URLs about equally well: {}. Use a more specific pattern or pass --first",
+            self.address,
+            self.candidates.join(", ")
+        )
+    }
+}
+
+impl std::error::Error for AmbiguousMatch {}
+
+/// Candidates scoring at least this fraction of the best one are close enough to ask about.
+const TIE_RATIO: f64 = 0.9;
+const MAX_TIED_CANDIDATES: usize = 9;
+
+/// The history matches for `address` that score close to the best one, best first, when
+/// there are several; opening the best silently could well pick the wrong one. Empty for
+/// URLs and for patterns with a clear winner.
+pub fn tied_matches(db: &dyn Database, address: &str) -> Result<Vec<String>> {
+    let InputType::FuzzyPattern(segments) = classify_input(address) else {
+        return Ok(Vec::new());
+    };
+    let matches = db.fuzzy_match_limit(&segments, MAX_TIED_CANDIDATES)?;
+    let Some((_, best, _)) = matches.first() else {
+        return Ok(Vec::new());
+    };
+
+    let tied: Vec<String> = matches
+        .iter()
+        .take_while(|(_, score, _)| *score >= best * TIE_RATIO)
+        .map(|(url, _, _)| url.clone())
+        .collect();
+    Ok(if tied.len() > 1 { tied } else { Vec::new() })
+}
+
+/// The `rank`-th history match for `address` (1 is the best), for when the best match is
+/// not the one wanted.
+pub fn ranked_match(db: &dyn Database, address: &str, rank: usize) -> Result<String> {
+    let InputType::FuzzyPattern(segments) = classify_input(address) else {
+        anyhow::bail!("'{}' is a URL, not a pattern to pick a match of", address);
+    };
+    if rank == 0 {
+        anyhow::bail!("Matches are numbered from 1");
+    }
+    let matches = db.fuzzy_match(&segments)?;
+    match matches.get(rank - 1) {
+        Some((url, _, _)) => Ok(url.clone()),
+        None if matches.is_empty() => anyhow::bail!("No matching URL found in history"),
+        None => anyhow::bail!(
+            "'{}' has only {} match{}; see them with `otot open --list`",
+            address,
+            matches.len(),
+            if matches.len() == 1 { "" } else { "es" }
+        ),
+    }
+}
+
+const EXPLAIN_CANDIDATES: usize = 5;
+
+/// Describes, step by step, how `resolve_address` treats an address and which URL wins.
+pub fn explain_address(db: &dyn Database, address: &str) -> Result<Vec<String>> {
+    if address.is_empty() {
+        anyhow::bail!("provided address must be a non-empty string");
+    }
+
+    let mut steps = Vec::new();
+    let cleaned = clean_pasted_address(address);
+    if cleaned != address.trim() {
+        steps.push(format!("Cleaned pasted input to '{}'", cleaned));
+    }
+
+    match classify_input(address) {
+        InputType::FullUrl(url) => {
+            let reason = if cleaned.contains("://") {
+                "it has an explicit scheme".to_string()
+            } else if url.port().is_some() {
+                "it has a port, so http was assumed".to_string()
+            } else if let Some(suffix) = url.host_str().and_then(known_suffix) {
+                format!("its host ends in '.{}', so https was assumed", suffix)
+            } else {
+                "it is a local address, so http was assumed".to_string()
+            };
+            steps.push(format!("Treated as a URL because {}", reason));
+            steps.push(format!("Resolved to {} without consulting history", url));
+        }
+        InputType::LocalPath(path) => {
+            steps.push("Treated as a local file because it is written as a path".to_string());
+            steps.push(format!(
+                "Resolved to {} without consulting history",
+                path.display()
+            ));
+        }
+        InputType::FuzzyPattern(segments) => {
+            steps.push(format!(
+                "Treated as a history pattern with segments {:?}",
+                segments
+            ));
+
+            if let Some((url, age)) = db.cached_match(&segments)? {
+                steps.push(format!(
+                    "Cache hit: resolved to {} {}s ago with nothing written since, so `open` skips scanning candidates",
+                    url, age
+                ));
             }
-            None => {
-                anyhow::bail!("No matching URL found in history");
+
+            let matches = db.fuzzy_match(&segments)?;
+            if matches.is_empty() {
+                steps.push("No history entry matches every segment".to_string());
+                return Ok(steps);
+            }
+
+            steps.push(format!(
+                "{} candidate(s) matched, best first:",
+                matches.len()
+            ));
+            for (url, score, _) in matches.iter().take(EXPLAIN_CANDIDATES) {
+                steps.push(format!("  {:>8.1}  {}", score, url));
             }
-        },
+            steps.push(format!(
+                "Resolved to {} (ranked first by matching.rank)",
+                matches[0].0
+            ));
+        }
+    }
+
+    Ok(steps)
+}
+
+pub fn open_address_impl(
+    opener: &dyn BrowserOpener,
+    db: &mut dyn Database,
+    address: &str,
+    preferred_browser: Option<&str>,
+) -> Result<()> {
+    open_address_with(opener, db, address, preferred_browser, |url| {
+        Ok(url.to_string())
+    })
+    .map(drop)
+}
+
+/// Like `open_address_impl`, but launches `rewrite(url)` instead, e.g. the URL wrapped in a
+/// viewer service. The visit is recorded for the resolved URL itself, which is returned.
+pub fn open_address_with(
+    opener: &dyn BrowserOpener,
+    db: &mut dyn Database,
+    address: &str,
+    preferred_browser: Option<&str>,
+    rewrite: impl FnOnce(&str) -> Result<String>,
+) -> Result<String> {
+    match resolve_address(db, address)? {
+        Some(url) => {
+            let launch = rewrite(&url)?;
+            opener.open(&launch, preferred_browser)?;
+            // Only launches that actually happened count towards the URL's score
+            let source = match classify_input(address) {
+                InputType::FuzzyPattern(_) => VisitSource::Fuzzy,
+                _ => VisitSource::Cli,
+            };
+            db.add_visit_from(&redact_secrets(&url), SystemTime::now(), source)?;
+            Ok(url)
+        }
+        None => {
+            db.record_miss(address.trim(), SystemTime::now())?;
+            anyhow::bail!("No matching URL found in history");
+        }
     }
 }
 
@@ -88,12 +387,68 @@ mod tests {
         (mock, captured)
     }
 
+    struct FailingOpener;
+
+    impl BrowserOpener for FailingOpener {
+        fn open(&self, _url: &str, _browser: Option<&str>) -> std::io::Result<()> {
+            Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                "no browser",
+            ))
+        }
+    }
+
     fn create_temp_db() -> (TempDir, SqliteDatabase) {
         let temp_dir = TempDir::new().unwrap();
         let db_path = temp_dir.path().join("test.db");
         let db = SqliteDatabase::open_at(&db_path).unwrap();
         (temp_dir, db)
     }
+    #[test]
+    fn close_matches_are_tied_and_clear_winners_are_not() {
+        let (_temp_dir, mut db) = create_temp_db();
+        let now = SystemTime::now();
+        db.add_visit("https://github.com/rust-lang/rust", now)
+            .unwrap();
+        db.add_visit("https://github.com/rust-fork/rust", now)
+            .unwrap();
+
+        let tied = tied_matches(&db, "gh/rust").unwrap();
+        assert_eq!(tied.len(), 2);
+
+        for _ in 0..5 {
+            db.add_visit("https://github.com/rust-lang/rust", now)
+                .unwrap();
+        }
+        assert!(tied_matches(&db, "gh/rust").unwrap().is_empty());
+        assert!(
+            tied_matches(&db, "https://github.com/rust-lang/rust")
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn ranked_match_picks_by_position() {
+        let (_temp_dir, mut db) = create_temp_db();
+        let now = SystemTime::now();
+        visit_times(&mut db, "https://github.com/rust-lang/rust", 3);
+        db.add_visit("https://github.com/rust-fork/rust", now)
+            .unwrap();
+
+        assert_eq!(
+            ranked_match(&db, "gh/rust", 1).unwrap(),
+            "https://github.com/rust-lang/rust"
+        );
+        assert_eq!(
+            ranked_match(&db, "gh/rust", 2).unwrap(),
+            "https://github.com/rust-fork/rust"
+        );
+        let err = ranked_match(&db, "gh/rust", 3).unwrap_err().to_string();
+        assert!(err.contains("only 2 matches"));
+        assert!(ranked_match(&db, "https://example.com", 1).is_err());
+    }
+
     #[test]
     fn empty_address_returns_error() {
         let (mock, _) = create_mock();
@@ -174,6 +529,135 @@ mod tests {
         );
     }
 
+    #[test]
+    fn resolve_address_returns_full_url_without_recording() {
+        let (_temp_dir, db) = create_temp_db();
+
+        let resolved = resolve_address(&db, "github.com/rust-lang").unwrap();
+
+        assert_eq!(resolved, Some("https://github.com/rust-lang".to_string()));
+        assert!(db.get_highest_usage_urls(10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn resolve_address_uses_history_for_patterns() {
+        let (_temp_dir, mut db) = create_temp_db();
+        db.add_visit("https://github.com/rust-lang/rust", SystemTime::now())
+            .unwrap();
+
+        let resolved = resolve_address(&db, "gh/rust").unwrap();
+
+        assert_eq!(
+            resolved,
+            Some("https://github.com/rust-lang/rust".to_string())
+        );
+    }
+
+    #[test]
+    fn open_address_with_launches_rewritten_url_but_records_original() {
+        let (mock, captured) = create_mock();
+        let (_temp_dir, mut db) = create_temp_db();
+
+        open_address_with(&mock, &mut db, "example.com/page", None, |url| {
+            Ok(format!("https://viewer.example/{}", url))
+        })
+        .unwrap();
+
+        assert_eq!(
+            captured.borrow().as_ref().map(|(url, _)| url.as_str()),
+            Some("https://viewer.example/https://example.com/page")
+        );
+        let recorded = db.get_highest_usage_urls(10).unwrap();
+        assert_eq!(recorded[0].url, "https://example.com/page");
+    }
+
+    #[test]
+    fn successful_opens_are_recorded_including_fuzzy_matches() {
+        let (mock, _) = create_mock();
+        let (_temp_dir, mut db) = create_temp_db();
+
+        open_address_impl(&mock, &mut db, "https://github.com/rust-lang/rust", None).unwrap();
+        open_address_impl(&mock, &mut db, "gh/rust", None).unwrap();
+
+        let history = db.get_highest_usage_urls(10).unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].url, "https://github.com/rust-lang/rust");
+        assert!(history[0].score > 1.0);
+    }
+
+    #[test]
+    fn credentials_are_opened_but_not_recorded() {
+        let (mock, captured) = create_mock();
+        let (_temp_dir, mut db) = create_temp_db();
+
+        open_address_impl(&mock, &mut db, "https://ci.example.com/?token=abc", None).unwrap();
+
+        assert_eq!(
+            captured.borrow().as_ref().map(|(url, _)| url.as_str()),
+            Some("https://ci.example.com/?token=abc")
+        );
+        let recorded = db.get_highest_usage_urls(10).unwrap();
+        assert_eq!(recorded[0].url, "https://ci.example.com/?token=REDACTED");
+    }
+
+    #[test]
+    fn local_files_open_as_file_urls() {
+        let (mock, captured) = create_mock();
+        let (temp_dir, mut db) = create_temp_db();
+        let report = temp_dir.path().canonicalize().unwrap().join("report.html");
+        std::fs::write(&report, "<title>Report</title>").unwrap();
+
+        open_address_impl(&mock, &mut db, report.to_str().unwrap(), None).unwrap();
+
+        let url = Url::from_file_path(&report).unwrap().to_string();
+        assert_eq!(
+            captured.borrow().as_ref().map(|(url, _)| url.as_str()),
+            Some(url.as_str())
+        );
+        assert_eq!(db.get_highest_usage_urls(10).unwrap()[0].url, url);
+    }
+
+    #[test]
+    fn failed_opens_are_not_recorded() {
+        let (_temp_dir, mut db) = create_temp_db();
+
+        let result = open_address_impl(&FailingOpener, &mut db, "https://github.com/", None);
+
+        assert!(result.is_err());
+        assert!(db.get_highest_usage_urls(10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn unresolved_patterns_are_recorded_as_misses() {
+        let (mock, captured) = create_mock();
+        let (_temp_dir, mut db) = create_temp_db();
+
+        assert!(open_address_impl(&mock, &mut db, "standup", None).is_err());
+        assert!(open_address_impl(&mock, &mut db, "standup", None).is_err());
+        open_address_impl(&mock, &mut db, "github.com", None).unwrap();
+
+        let misses = db.misses().unwrap();
+        assert_eq!(misses.len(), 1);
+        assert_eq!(misses[0].pattern, "standup");
+        assert_eq!(misses[0].count, 2);
+        assert_eq!(
+            captured.borrow().as_ref().map(|(url, _)| url.as_str()),
+            Some("https://github.com/")
+        );
+    }
+
+    fn visit_times(db: &mut SqliteDatabase, url: &str, times: usize) {
+        for _ in 0..times {
+            db.add_visit(url, SystemTime::now()).unwrap();
+        }
+    }
+
+    #[test]
+    fn resolve_address_returns_none_without_match() {
+        let (_temp_dir, db) = create_temp_db();
+        assert_eq!(resolve_address(&db, "gh/rust").unwrap(), None);
+    }
+
     #[test]
     fn preserves_query_parameters() {
         let (mock, captured) = create_mock();
@@ -212,4 +696,93 @@ mod tests {
             Some(("https://github.com/search?q=rust#results".to_string(), None))
         );
     }
+
+    #[test]
+    fn explain_reports_url_classification() {
+        let (_temp_dir, db) = create_temp_db();
+
+        let steps = explain_address(&db, "<github.com/rust-lang>.").unwrap();
+
+        assert!(steps[0].contains("Cleaned pasted input"));
+        assert!(steps[1].contains("so https was assumed"));
+        assert!(steps[2].contains("https://github.com/rust-lang"));
+    }
+
+    #[test]
+    fn explain_lists_history_candidates_and_winner() {
+        let (_temp_dir, mut db) = create_temp_db();
+        db.add_visit("https://github.com/rust-lang/rust", SystemTime::now())
+            .unwrap();
+
+        let steps = explain_address(&db, "gh/rust").unwrap();
+
+        assert!(steps[0].contains("history pattern"));
+        assert!(steps[1].contains("1 candidate(s)"));
+        assert!(
+            steps
+                .last()
+                .unwrap()
+                .contains("Resolved to https://github.com/rust-lang/rust")
+        );
+    }
+
+    #[test]
+    fn explain_reports_cache_hits() {
+        let (_temp_dir, mut db) = create_temp_db();
+        db.add_visit("https://github.com/rust-lang/rust", SystemTime::now())
+            .unwrap();
+        resolve_address(&db, "gh/rust").unwrap();
+
+        let steps = explain_address(&db, "gh/rust").unwrap();
+
+        assert!(steps[1].starts_with("Cache hit: resolved to https://github.com/rust-lang/rust"));
+    }
+
+    #[test]
+    fn explain_reports_missing_match() {
+        let (_temp_dir, db) = create_temp_db();
+
+        let steps = explain_address(&db, "nothing/here").unwrap();
+
+        assert_eq!(
+            steps.last().unwrap(),
+            "No history entry matches every segment"
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn launches_that_fail_straight_away_are_reported() {
+        let grace = Some(Duration::from_secs(5));
+        let command = |program: &str, args: &[&str]| {
+            let mut command = Command::new(program);
+            command.args(args);
+            command
+        };
+
+        let error = launch(command("false", &[]), grace).unwrap_err();
+        let failed = error.get_ref().unwrap().downcast_ref::<LaunchFailed>();
+        assert_eq!(failed.unwrap().program, "false");
+        assert!(error.to_string().contains("was found but failed to start"));
+
+        assert!(launch(command("true", &[]), grace).is_ok());
+        assert!(launch(command("false", &[]), None).is_ok());
+        let started = Instant::now();
+        assert!(launch(command("sleep", &["5"]), Some(Duration::from_millis(100))).is_ok());
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        let missing = launch(command("otot-no-such-browser", &[]), grace).unwrap_err();
+        assert_eq!(missing.kind(), io::ErrorKind::NotFound);
+        assert_eq!(missing.to_string(), "otot-no-such-browser was not found");
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn verified_openers_report_failed_launches() {
+        let openers = BTreeMap::from([("mailto".to_string(), "false {url}".to_string())]);
+        let opener = SystemBrowserOpener::new(openers).verify_launch(true);
+
+        let error = opener.open("mailto:someone@example.com", None).unwrap_err();
+        assert!(error.get_ref().unwrap().is::<LaunchFailed>());
+    }
 }
@@ -0,0 +1,1575 @@
+use anyhow::{Context, Result};
+use clap::{Args, Subcommand, ValueEnum};
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::database::DEFAULT_SLOW_QUERY_THRESHOLD;
+use crate::dispatch::{DIRECTORY_OPENER, validate_opener};
+use crate::permissions::write_private_file;
+use crate::push::validate_relay;
+use crate::rank::RankStrategy;
+use crate::shell::lossy_text;
+use crate::units::{format_size, parse_size};
+use crate::url_classify::{ClassifyOptions, Separators};
+
+/// Bump together with a new step in `migrate_table` whenever the file layout changes.
+pub const CONFIG_VERSION: u32 = 2;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OtotConfig {
+    pub version: u32,
+    #[serde(default)]
+    pub browser: BrowserConfig,
+    #[serde(default)]
+    pub database: DatabaseConfig,
+    #[serde(default)]
+    pub focus: FocusConfig,
+    #[serde(default)]
+    pub audit: AuditConfig,
+    #[serde(default)]
+    pub push: PushConfig,
+    #[serde(default)]
+    pub network: NetworkConfig,
+    #[serde(default)]
+    pub matching: MatchingConfig,
+    #[serde(default)]
+    pub classify: ClassifyConfig,
+    #[serde(default)]
+    pub preview: PreviewConfig,
+    #[serde(default)]
+    pub limits: LimitsConfig,
+    #[serde(default)]
+    pub history: HistoryConfig,
+    #[serde(default)]
+    pub log: LogConfig,
+    /// Named URL templates for `open --as`, in addition to the built-in viewers
+    #[serde(default)]
+    pub viewers: BTreeMap<String, String>,
+    /// Commands that open URLs of a scheme, or local directories under `directory`, instead
+    /// of the browser, e.g. `ssh = "kitty ssh {url}"`
+    #[serde(default)]
+    pub openers: BTreeMap<String, String>,
+}
+
+impl Default for OtotConfig {
+    fn default() -> Self {
+        Self {
+            version: CONFIG_VERSION,
+            browser: BrowserConfig::default(),
+            database: DatabaseConfig::default(),
+            focus: FocusConfig::default(),
+            audit: AuditConfig::default(),
+            push: PushConfig::default(),
+            network: NetworkConfig::default(),
+            matching: MatchingConfig::default(),
+            classify: ClassifyConfig::default(),
+            preview: PreviewConfig::default(),
+            limits: LimitsConfig::default(),
+            history: HistoryConfig::default(),
+            log: LogConfig::default(),
+            viewers: BTreeMap::new(),
+            openers: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct BrowserConfig {
+    pub preferred: Option<String>,
+    /// Check that the browser actually started instead of trusting the launcher
+    #[serde(default)]
+    pub verify_launch: bool,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct DatabaseConfig {
+    pub slow_query_ms: Option<u64>,
+    /// Compact the database file whenever `otot prune` deletes something
+    #[serde(default)]
+    pub auto_vacuum: bool,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct LogConfig {
+    /// Log level per module on top of `-v`, e.g. `"otot::database" = "debug"`
+    #[serde(default)]
+    pub levels: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct FocusConfig {
+    /// Hosts that no command opens or pushes while a focus session is running
+    #[serde(default)]
+    pub distractions: Vec<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct AuditConfig {
+    /// Log every launched URL and require `--reason` for opens
+    #[serde(default)]
+    pub enabled: bool,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PushConfig {
+    /// ntfy-compatible topic URL that `otot push` sends to, set up by `otot pair`
+    pub relay: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct NetworkConfig {
+    /// Hosts only reachable over the VPN; `open` checks they respond before launching them
+    #[serde(default)]
+    pub requires_vpn: Vec<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct MatchingConfig {
+    /// Extra characters that split typed patterns into segments, e.g. `"."` for
+    /// `github.rust.issues`; `/` and whitespace always do
+    pub separators: Option<String>,
+    /// What `open` does without a terminal when several URLs match about equally well
+    #[serde(default)]
+    pub on_ambiguous: AmbiguityPolicy,
+    /// How URLs matching a pattern are ordered
+    #[serde(default)]
+    pub rank: RankStrategy,
+    /// Match URLs flagged as likely garbage too, e.g. OAuth callbacks and logout links
+    #[serde(default)]
+    pub include_garbage: bool,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ClassifyConfig {
+    /// Host names without a public suffix, such as `wiki` or `jenkins`, that `open` treats
+    /// as sites rather than history patterns
+    #[serde(default)]
+    pub known_hosts: Vec<String>,
+    /// URL schemes besides http, https, file, mailto and ssh that `open` launches, e.g. `slack`
+    #[serde(default)]
+    pub schemes: Vec<String>,
+    /// `http` or `https` for addresses typed without a scheme whose host matches, e.g.
+    /// `"*.internal" = "http"`. Other hosts get https unless a port is given.
+    #[serde(default)]
+    pub host_schemes: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PreviewConfig {
+    /// Fetch the title and HTTP status of the pages offered when matches tie. Off by
+    /// default, since it contacts every candidate's server.
+    #[serde(default)]
+    pub enabled: bool,
+    /// Fetch the title of each page the first time it is opened, so patterns can match
+    /// words from it. Off by default for the same reason.
+    #[serde(default)]
+    pub fetch_titles: bool,
+}
+
+/// Sizes past which `open` suggests cleaning up, at most once a week. `0` turns a check off.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct LimitsConfig {
+    pub max_urls: Option<u64>,
+    /// Database file size in bytes
+    pub max_size: Option<u64>,
+    /// URLs sharing one last segment; a pattern ending in it has to score all of them
+    pub max_segment_candidates: Option<u64>,
+}
+
+impl LimitsConfig {
+    pub fn max_urls(&self) -> u64 {
+        self.max_urls.unwrap_or(50_000)
+    }
+
+    pub fn max_size(&self) -> u64 {
+        self.max_size.unwrap_or(100 << 20)
+    }
+
+    pub fn max_segment_candidates(&self) -> u64 {
+        self.max_segment_candidates.unwrap_or(500)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum AmbiguityPolicy {
+    /// Open the best match
+    #[default]
+    First,
+    /// Fail with exit status 3 and list the candidates
+    Fail,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct HistoryConfig {
+    /// Saved `otot history` reports, run with `otot history run <name>`
+    #[serde(default)]
+    pub queries: BTreeMap<String, HistoryQuery>,
+}
+
+/// Filters and output options of an `otot history` report.
+#[derive(Args, Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct HistoryQuery {
+    /// Only URLs on this domain or its subdomains
+    #[arg(long, value_parser = lossy_text())]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub domain: Option<String>,
+
+    /// Only URLs visited within this window, e.g. `1d`, `2w`
+    #[arg(long, value_name = "DURATION", value_parser = lossy_text())]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub since: Option<String>,
+
+    /// Only URLs visited for the first time within this window, e.g. `1d`, `2w`
+    #[arg(long, value_name = "DURATION", value_parser = lossy_text())]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub new_since: Option<String>,
+
+    /// Show at most this many URLs [default: 20]
+    #[arg(short = 'n', long)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+
+    /// Print the entries as JSON
+    #[arg(long)]
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub json: bool,
+
+    /// Show how each URL's frecency is computed from its score and last visit
+    #[arg(long, conflicts_with = "json")]
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub show_frecency_breakdown: bool,
+}
+
+impl HistoryQuery {
+    /// This query with every option set in `overrides` replacing its own.
+    pub fn with_overrides(&self, overrides: &HistoryQuery) -> HistoryQuery {
+        HistoryQuery {
+            domain: overrides.domain.clone().or_else(|| self.domain.clone()),
+            since: overrides.since.clone().or_else(|| self.since.clone()),
+            new_since: overrides
+                .new_since
+                .clone()
+                .or_else(|| self.new_since.clone()),
+            limit: overrides.limit.or(self.limit),
+            json: overrides.json || self.json,
+            show_frecency_breakdown: overrides.show_frecency_breakdown
+                || self.show_frecency_breakdown,
+        }
+    }
+}
+
+const CONFIG_KEYS: &[&str] = &[
+    "browser.preferred",
+    "browser.verify_launch",
+    "database.slow_query_ms",
+    "database.auto_vacuum",
+    "focus.distractions",
+    "audit.enabled",
+    "push.relay",
+    "network.requires_vpn",
+    "matching.separators",
+    "matching.on_ambiguous",
+    "matching.rank",
+    "matching.include_garbage",
+    "classify.known_hosts",
+    "classify.schemes",
+    "classify.host_schemes.<host>",
+    "preview.enabled",
+    "preview.fetch_titles",
+    "limits.max_urls",
+    "limits.max_size",
+    "limits.max_segment_candidates",
+    "viewers.<name>",
+    "openers.<scheme>",
+    "log.levels.<module>",
+];
+
+/// Flat keys from config version 1, still accepted by `otot config get/set`.
+const LEGACY_KEYS: &[(&str, &str)] = &[
+    ("preferred_browser", "browser.preferred"),
+    ("slow_query_ms", "database.slow_query_ms"),
+    ("distractions", "focus.distractions"),
+];
+
+fn canonical_key(key: &str) -> &str {
+    LEGACY_KEYS
+        .iter()
+        .find(|(legacy, _)| *legacy == key)
+        .map_or(key, |(_, current)| current)
+}
+
+fn unknown_config_key(key: &str) -> anyhow::Error {
+    anyhow::anyhow!(
+        "Unknown config key: '{}'. Valid keys: {}",
+        key,
+        CONFIG_KEYS.join(", ")
+    )
+}
+
+fn viewer_name(key: &str) -> Option<&str> {
+    key.strip_prefix("viewers.").filter(|name| !name.is_empty())
+}
+
+fn opener_kind(key: &str) -> Option<&str> {
+    key.strip_prefix("openers.").filter(|kind| !kind.is_empty())
+}
+
+fn log_module(key: &str) -> Option<&str> {
+    key.strip_prefix("log.levels.")
+        .filter(|module| !module.is_empty())
+}
+
+/// The module whose log output a subsystem name such as `database` stands for, i.e.
+/// `otot::database`; module paths such as `otot::database` are kept as given.
+pub fn subsystem_module(name: &str) -> String {
+    let name = name.trim();
+    if name.contains("::") || name == "otot" {
+        name.to_string()
+    } else {
+        format!("otot::{}", name)
+    }
+}
+
+fn host_scheme_pattern(key: &str) -> Option<&str> {
+    key.strip_prefix("classify.host_schemes.")
+        .filter(|host| !host.is_empty())
+}
+
+/// Keys naming one entry of a table, such as `viewers.archive`, which have no default.
+fn is_table_entry(key: &str) -> bool {
+    viewer_name(key).is_some()
+        || host_scheme_pattern(key).is_some()
+        || opener_kind(key).is_some()
+        || log_module(key).is_some()
+}
+
+/// Accepts the usual spellings of a boolean; an empty value means `false`.
+fn parse_bool(key: &str, value: &str) -> Result<bool> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "true" | "yes" | "on" | "1" => Ok(true),
+        "false" | "no" | "off" | "0" | "" => Ok(false),
+        _ => anyhow::bail!(
+            "Invalid value for {}: '{}' (expected a boolean: true or false)",
+            key,
+            value
+        ),
+    }
+}
+
+/// Milliseconds from `250`, `250ms` or `2s`.
+fn parse_millis(key: &str, value: &str) -> Result<u64> {
+    let value = value.trim();
+    let (number, factor) = match (value.strip_suffix("ms"), value.strip_suffix('s')) {
+        (Some(number), _) => (number, 1),
+        (None, Some(number)) => (number, 1000),
+        (None, None) => (value, 1),
+    };
+    number
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .and_then(|number| number.checked_mul(factor))
+        .with_context(|| {
+            format!(
+                "Invalid value for {}: '{}' (expected a duration such as 250ms or 2s)",
+                key, value
+            )
+        })
+}
+
+/// A whole number such as a row count.
+fn parse_count(key: &str, value: &str) -> Result<u64> {
+    value.trim().parse().with_context(|| {
+        format!(
+            "Invalid value for {}: '{}' (expected a whole number)",
+            key, value
+        )
+    })
+}
+
+fn parse_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// A scheme as RFC 3986 spells it: a letter, then letters, digits, `+`, `-` or `.`.
+fn is_scheme_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphabetic())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+}
+
+impl OtotConfig {
+    pub fn slow_query_threshold(&self) -> Duration {
+        self.database
+            .slow_query_ms
+            .map(Duration::from_millis)
+            .unwrap_or(DEFAULT_SLOW_QUERY_THRESHOLD)
+    }
+
+    pub fn separators(&self) -> Separators {
+        let Some(spec) = self.matching.separators.as_deref() else {
+            return Separators::default();
+        };
+        Separators::parse(spec).unwrap_or_else(|e| {
+            warn!("Ignoring matching.separators: {:#}", e);
+            Separators::default()
+        })
+    }
+
+    /// How typed addresses are told apart from patterns.
+    pub fn classify_options(&self) -> ClassifyOptions {
+        ClassifyOptions {
+            separators: self.separators(),
+            known_hosts: self.classify.known_hosts.clone(),
+            // A scheme with its own opener is one the user means to open
+            schemes: self
+                .classify
+                .schemes
+                .iter()
+                .chain(self.openers.keys().filter(|kind| *kind != DIRECTORY_OPENER))
+                .cloned()
+                .collect(),
+            host_schemes: self.classify.host_schemes.clone(),
+        }
+    }
+
+    /// Renders the current value of `key`, or `None` when it is not set.
+    pub fn get_value(&self, key: &str) -> Result<Option<String>> {
+        let value = match canonical_key(key) {
+            "browser.preferred" => self.browser.preferred.clone(),
+            "database.slow_query_ms" => self.database.slow_query_ms.map(|ms| format!("{}ms", ms)),
+            "focus.distractions" => Some(self.focus.distractions.join(","))
+                .filter(|_| !self.focus.distractions.is_empty()),
+            "browser.verify_launch" => Some(self.browser.verify_launch.to_string()),
+            "database.auto_vacuum" => Some(self.database.auto_vacuum.to_string()),
+            "audit.enabled" => Some(self.audit.enabled.to_string()),
+            "preview.enabled" => Some(self.preview.enabled.to_string()),
+            "matching.include_garbage" => Some(self.matching.include_garbage.to_string()),
+            "preview.fetch_titles" => Some(self.preview.fetch_titles.to_string()),
+            "limits.max_urls" => self.limits.max_urls.map(|n| n.to_string()),
+            "limits.max_size" => self.limits.max_size.map(format_size),
+            "limits.max_segment_candidates" => {
+                self.limits.max_segment_candidates.map(|n| n.to_string())
+            }
+            "push.relay" => self.push.relay.clone(),
+            "matching.separators" => self.matching.separators.clone(),
+            "matching.on_ambiguous" => Some(
+                self.matching
+                    .on_ambiguous
+                    .to_possible_value()
+                    .map_or_else(String::new, |value| value.get_name().to_string()),
+            ),
+            "matching.rank" => Some(
+                self.matching
+                    .rank
+                    .to_possible_value()
+                    .map_or_else(String::new, |value| value.get_name().to_string()),
+            ),
+            "network.requires_vpn" => Some(self.network.requires_vpn.join(","))
+                .filter(|_| !self.network.requires_vpn.is_empty()),
+            "classify.known_hosts" => Some(self.classify.known_hosts.join(","))
+                .filter(|_| !self.classify.known_hosts.is_empty()),
+            "classify.schemes" => {
+                Some(self.classify.schemes.join(",")).filter(|_| !self.classify.schemes.is_empty())
+            }
+            "viewers" => Some(
+                self.viewers
+                    .iter()
+                    .map(|(name, template)| format!("{} = {}", name, template))
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            )
+            .filter(|_| !self.viewers.is_empty()),
+            key if let Some(name) = viewer_name(key) => self.viewers.get(name).cloned(),
+            "openers" => Some(
+                self.openers
+                    .iter()
+                    .map(|(kind, command)| format!("{} = {}", kind, command))
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            )
+            .filter(|_| !self.openers.is_empty()),
+            key if let Some(kind) = opener_kind(key) => {
+                self.openers.get(&kind.to_ascii_lowercase()).cloned()
+            }
+            "classify.host_schemes" => Some(
+                self.classify
+                    .host_schemes
+                    .iter()
+                    .map(|(host, scheme)| format!("{} = {}", host, scheme))
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            )
+            .filter(|_| !self.classify.host_schemes.is_empty()),
+            key if let Some(host) = host_scheme_pattern(key) => {
+                self.classify.host_schemes.get(host).cloned()
+            }
+            "log.levels" => Some(
+                self.log
+                    .levels
+                    .iter()
+                    .map(|(module, level)| format!("{} = {}", module, level))
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            )
+            .filter(|_| !self.log.levels.is_empty()),
+            key if let Some(module) = log_module(key) => {
+                self.log.levels.get(&subsystem_module(module)).cloned()
+            }
+            _ => return Err(unknown_config_key(key)),
+        };
+
+        Ok(value)
+    }
+
+    /// The value of `key` as typed JSON, e.g. `true` or `["reddit.com"]`; `null` when unset.
+    pub fn get_json(&self, key: &str) -> Result<serde_json::Value> {
+        let value = self.get_value(key)?;
+        // Host patterns and module paths contain separators of their own, so they can't be
+        // looked up by path
+        if host_scheme_pattern(key).is_some() || log_module(key).is_some() {
+            return Ok(value.map(serde_json::Value::String).unwrap_or_default());
+        }
+        let pointer = format!("/{}", canonical_key(key).replacen('.', "/", 1));
+        Ok(serde_json::to_value(self)?
+            .pointer(&pointer)
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    /// Sets `key` from its string form. An empty value clears the setting.
+    pub fn set_value(&mut self, key: &str, new: &str) -> Result<()> {
+        match canonical_key(key) {
+            "browser.preferred" => {
+                self.browser.preferred = Some(new.to_string()).filter(|s| !s.is_empty());
+            }
+            "database.slow_query_ms" => {
+                self.database.slow_query_ms = if new.is_empty() {
+                    None
+                } else {
+                    Some(parse_millis("database.slow_query_ms", new)?)
+                };
+            }
+            "focus.distractions" => self.focus.distractions = parse_list(new),
+            "browser.verify_launch" => {
+                self.browser.verify_launch = parse_bool("browser.verify_launch", new)?
+            }
+            "database.auto_vacuum" => {
+                self.database.auto_vacuum = parse_bool("database.auto_vacuum", new)?
+            }
+            "audit.enabled" => self.audit.enabled = parse_bool("audit.enabled", new)?,
+            "preview.enabled" => self.preview.enabled = parse_bool("preview.enabled", new)?,
+            "matching.include_garbage" => {
+                self.matching.include_garbage = parse_bool("matching.include_garbage", new)?
+            }
+            "preview.fetch_titles" => {
+                self.preview.fetch_titles = parse_bool("preview.fetch_titles", new)?
+            }
+            key @ ("limits.max_urls" | "limits.max_segment_candidates") => {
+                let limit = if new.is_empty() {
+                    None
+                } else {
+                    Some(parse_count(key, new)?)
+                };
+                match key {
+                    "limits.max_urls" => self.limits.max_urls = limit,
+                    _ => self.limits.max_segment_candidates = limit,
+                }
+            }
+            "limits.max_size" => {
+                self.limits.max_size = if new.is_empty() {
+                    None
+                } else {
+                    Some(parse_size(new).context("Invalid value for limits.max_size")?)
+                };
+            }
+            "network.requires_vpn" => self.network.requires_vpn = parse_list(new),
+            "classify.known_hosts" => self.classify.known_hosts = parse_list(new),
+            "classify.schemes" => {
+                let schemes = parse_list(new);
+                if let Some(bad) = schemes.iter().find(|scheme| !is_scheme_name(scheme)) {
+                    anyhow::bail!(
+                        "'{}' is not a URL scheme; give just the name, e.g. slack",
+                        bad
+                    );
+                }
+                self.classify.schemes = schemes.iter().map(|s| s.to_lowercase()).collect();
+            }
+            "matching.separators" => {
+                Separators::parse(new).context("Invalid value for matching.separators")?;
+                self.matching.separators = Some(new.to_string()).filter(|s| !s.is_empty());
+            }
+            "matching.on_ambiguous" => {
+                self.matching.on_ambiguous = if new.is_empty() {
+                    AmbiguityPolicy::default()
+                } else {
+                    AmbiguityPolicy::from_str(new.trim(), true).map_err(|_| {
+                        anyhow::anyhow!(
+                            "Invalid value for matching.on_ambiguous: '{}' (expected first or fail)",
+                            new
+                        )
+                    })?
+                };
+            }
+            "matching.rank" => {
+                self.matching.rank = if new.is_empty() {
+                    RankStrategy::default()
+                } else {
+                    RankStrategy::from_str(new.trim(), true).map_err(|_| {
+                        anyhow::anyhow!(
+                            "Invalid value for matching.rank: '{}' (expected combined, frecency, recency or score)",
+                            new
+                        )
+                    })?
+                };
+            }
+            "push.relay" => {
+                if !new.is_empty() {
+                    validate_relay(new)?;
+                }
+                self.push.relay = Some(new.to_string()).filter(|s| !s.is_empty());
+            }
+            "viewers" => {
+                anyhow::bail!("Set viewers one at a time with viewers.<name>, e.g. viewers.archive")
+            }
+            "openers" => {
+                anyhow::bail!("Set openers one at a time with openers.<scheme>, e.g. openers.ssh")
+            }
+            key if let Some(kind) = opener_kind(key) => {
+                if kind != DIRECTORY_OPENER && !is_scheme_name(kind) {
+                    anyhow::bail!(
+                        "'{}' is not a URL scheme; use e.g. openers.ssh, or openers.directory for folders",
+                        kind
+                    );
+                }
+                let kind = kind.to_ascii_lowercase();
+                if new.trim().is_empty() {
+                    self.openers.remove(&kind);
+                } else {
+                    validate_opener(new)?;
+                    self.openers.insert(kind, new.trim().to_string());
+                }
+            }
+            "classify.host_schemes" => anyhow::bail!(
+                "Set host schemes one at a time with classify.host_schemes.<host>, e.g. classify.host_schemes.*.internal"
+            ),
+            key if let Some(host) = host_scheme_pattern(key) => match new.trim() {
+                "" => {
+                    self.classify.host_schemes.remove(host);
+                }
+                scheme @ ("http" | "https") => {
+                    self.classify
+                        .host_schemes
+                        .insert(host.to_ascii_lowercase(), scheme.to_string());
+                }
+                _ => anyhow::bail!(
+                    "Invalid value for {}: '{}' (expected http or https)",
+                    key,
+                    new
+                ),
+            },
+            key if let Some(name) = viewer_name(key) => {
+                if new.is_empty() {
+                    self.viewers.remove(name);
+                } else if !new.contains("{url") {
+                    anyhow::bail!(
+                        "Viewer templates must contain {{url}} or {{url_encoded}}, e.g. https://archive.ph/{{url}}"
+                    );
+                } else {
+                    self.viewers.insert(name.to_string(), new.to_string());
+                }
+            }
+            "log.levels" => anyhow::bail!(
+                "Set log levels one module at a time with log.levels.<module>, e.g. log.levels.database"
+            ),
+            key if let Some(module) = log_module(key) => {
+                let module = subsystem_module(module);
+                if new.trim().is_empty() {
+                    self.log.levels.remove(&module);
+                } else {
+                    let level: log::LevelFilter = new.trim().parse().map_err(|_| {
+                        anyhow::anyhow!(
+                            "Invalid value for {}: '{}' (expected off, error, warn, info, debug or trace)",
+                            key,
+                            new
+                        )
+                    })?;
+                    self.log
+                        .levels
+                        .insert(module, level.as_str().to_ascii_lowercase());
+                }
+            }
+            _ => return Err(unknown_config_key(key)),
+        }
+
+        Ok(())
+    }
+
+    /// Reverts `key` to its default value.
+    pub fn unset_value(&mut self, key: &str) -> Result<()> {
+        let default = OtotConfig::default().get_value(key)?;
+        self.set_value(key, default.as_deref().unwrap_or(""))
+    }
+
+    /// Every settable key with its current value, configured viewers, openers and host
+    /// schemes included.
+    pub fn values(&self) -> Result<Vec<(String, Option<String>)>> {
+        let keys = CONFIG_KEYS
+            .iter()
+            .filter(|key| !is_table_entry(key))
+            .map(|key| key.to_string())
+            .chain(
+                self.classify
+                    .host_schemes
+                    .keys()
+                    .map(|host| format!("classify.host_schemes.{}", host)),
+            )
+            .chain(self.viewers.keys().map(|name| format!("viewers.{}", name)))
+            .chain(self.openers.keys().map(|kind| format!("openers.{}", kind)))
+            .chain(
+                self.log
+                    .levels
+                    .keys()
+                    .map(|module| format!("log.levels.{}", module)),
+            );
+        keys.map(|key| {
+            let value = self.get_value(&key)?;
+            Ok((key, value))
+        })
+        .collect()
+    }
+}
+
+pub fn config_file_path(custom: Option<&Path>) -> Result<PathBuf> {
+    match custom {
+        Some(path) => Ok(path.to_path_buf()),
+        None => {
+            confy::get_configuration_file_path("otot", None).context("Failed to get config path")
+        }
+    }
+}
+
+/// Loads the config, upgrading files written by older versions in place. The original file
+/// is kept next to it as `<name>.v<old version>.bak`.
+pub fn load_config(custom: Option<&Path>) -> Result<OtotConfig> {
+    let path = config_file_path(custom)?;
+    if !path.exists() {
+        return Ok(OtotConfig::default());
+    }
+
+    let text = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read config file {}", path.display()))?;
+    let (config, version) = parse_config(&text)
+        .with_context(|| format!("Failed to load config file {}", path.display()))?;
+    if version == CONFIG_VERSION {
+        return Ok(config);
+    }
+
+    let backup = backup_path(&path, version);
+    // The config can name internal hosts and the push relay topic, so the copy is private
+    write_private_file(&backup, text.as_bytes())
+        .with_context(|| format!("Failed to back up config to {}", backup.display()))?;
+    store_config(Some(&path), &config)?;
+    info!(
+        "Migrated config from version {} to {} (backup at {})",
+        version,
+        CONFIG_VERSION,
+        backup.display()
+    );
+
+    Ok(config)
+}
+
+/// Parses the contents of a config file, upgrading older layouts in memory. Also returns
+/// the version the text was written with.
+pub fn parse_config(text: &str) -> Result<(OtotConfig, u32)> {
+    let mut table: toml::Table = text.parse().context("Failed to parse configuration")?;
+
+    let version = table_version(&table);
+    if version > CONFIG_VERSION {
+        anyhow::bail!(
+            "It was written by a newer otot (config version {}); this build understands up to version {}",
+            version,
+            CONFIG_VERSION
+        );
+    }
+
+    if version < CONFIG_VERSION {
+        migrate_table(&mut table, version);
+    }
+    let config = table.try_into().context("Failed to load configuration")?;
+    Ok((config, version))
+}
+
+pub fn store_config(custom: Option<&Path>, config: &OtotConfig) -> Result<()> {
+    confy::store_path(config_file_path(custom)?, config).context("Failed to save configuration")
+}
+
+fn table_version(table: &toml::Table) -> u32 {
+    table
+        .get("version")
+        .and_then(toml::Value::as_integer)
+        .map_or(1, |v| v as u32)
+}
+
+fn backup_path(path: &Path, version: u32) -> PathBuf {
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "config.toml".to_string());
+    path.with_file_name(format!("{}.v{}.bak", file_name, version))
+}
+
+fn migrate_table(table: &mut toml::Table, version: u32) {
+    if version < 2 {
+        debug!("Applying config migration v2: group flat keys into sections");
+        for (legacy, current) in LEGACY_KEYS {
+            if let Some(value) = table.remove(*legacy) {
+                let (section, key) = current.split_once('.').expect("keys are dotted");
+                let entry = table
+                    .entry(section)
+                    .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+                if let Some(section) = entry.as_table_mut() {
+                    section.insert(key.to_string(), value);
+                }
+            }
+        }
+    }
+
+    table.insert(
+        "version".to_string(),
+        toml::Value::Integer(CONFIG_VERSION.into()),
+    );
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigAction {
+    Set {
+        #[arg(short, long, value_parser = lossy_text())]
+        key: String,
+
+        #[arg(short, long, value_parser = lossy_text())]
+        new: String,
+    },
+    /// Print a value, or with `--json` and no key the whole effective config
+    Get {
+        #[arg(short, long, required_unless_present = "json", value_parser = lossy_text())]
+        key: Option<String>,
+
+        /// Print as typed JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Print every key with its current and default value
+    List,
+    /// Revert a key to its default value
+    Unset {
+        #[arg(value_parser = lossy_text())]
+        key: String,
+    },
+    Path,
+}
+
+pub fn handle_config_action(action: ConfigAction) -> Result<()> {
+    handle_config_action_with_config(action, None)
+}
+pub fn handle_config_action_with_config(
+    action: ConfigAction,
+    config_path: Option<&Path>,
+) -> Result<()> {
+    match action {
+        ConfigAction::Get { key, json } => {
+            let config = load_config(config_path)?;
+
+            match (key, json) {
+                (Some(key), true) => println!("{}", config.get_json(&key)?),
+                (None, _) => println!("{}", serde_json::to_string_pretty(&config)?),
+                (Some(key), false) => match config.get_value(&key)? {
+                    Some(value) => println!("{}", value),
+                    None => println!("(not set)"),
+                },
+            }
+
+            Ok(())
+        }
+
+        ConfigAction::Set { key, new } => {
+            let mut config = load_config(config_path)?;
+
+            config.set_value(&key, &new)?;
+            store_config(config_path, &config)?;
+
+            info!(
+                "Set {} to: {}",
+                key,
+                config.get_value(&key)?.as_deref().unwrap_or("(none)")
+            );
+            println!("Configuration updated");
+
+            Ok(())
+        }
+
+        ConfigAction::List => {
+            let config = load_config(config_path)?;
+            let defaults = OtotConfig::default();
+            let shown = |value: Option<String>| value.unwrap_or_else(|| "(not set)".to_string());
+
+            for (key, value) in config.values()? {
+                let default = if is_table_entry(&key) {
+                    None
+                } else {
+                    defaults.get_value(&key)?
+                };
+                println!(
+                    "{:<24} {}  (default: {})",
+                    key,
+                    shown(value),
+                    shown(default)
+                );
+            }
+
+            Ok(())
+        }
+
+        ConfigAction::Unset { key } => {
+            let mut config = load_config(config_path)?;
+
+            config.unset_value(&key)?;
+            store_config(config_path, &config)?;
+
+            info!("Reverted {} to its default", key);
+            println!("Configuration updated");
+
+            Ok(())
+        }
+
+        ConfigAction::Path => {
+            println!("{}", config_file_path(config_path)?.display());
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_fs::TempDir;
+    #[test]
+    fn config_set_and_get_preferred_browser() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+
+        handle_config_action_with_config(
+            ConfigAction::Set {
+                key: "preferred_browser".to_string(),
+                new: "firefox".to_string(),
+            },
+            Some(&config_path),
+        )
+        .unwrap();
+
+        let config: OtotConfig = load_config(Some(&config_path)).unwrap();
+        assert_eq!(config.browser.preferred, Some("firefox".to_string()));
+
+        let result = handle_config_action_with_config(
+            ConfigAction::Get {
+                key: Some("preferred_browser".to_string()),
+                json: false,
+            },
+            Some(&config_path),
+        );
+        assert!(result.is_ok());
+    }
+    #[test]
+    fn config_set_audit_enabled_writes_audit_section() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+
+        handle_config_action_with_config(
+            ConfigAction::Set {
+                key: "audit.enabled".to_string(),
+                new: "true".to_string(),
+            },
+            Some(&config_path),
+        )
+        .unwrap();
+
+        let config: OtotConfig = load_config(Some(&config_path)).unwrap();
+        assert!(config.audit.enabled);
+        assert!(
+            std::fs::read_to_string(&config_path)
+                .unwrap()
+                .contains("[audit]")
+        );
+    }
+    #[test]
+    fn config_set_audit_enabled_rejects_non_boolean() {
+        let mut config = OtotConfig::default();
+        assert!(config.set_value("audit.enabled", "yes please").is_err());
+    }
+    #[test]
+    fn config_set_empty_value_clears_setting() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+
+        handle_config_action_with_config(
+            ConfigAction::Set {
+                key: "preferred_browser".to_string(),
+                new: "firefox".to_string(),
+            },
+            Some(&config_path),
+        )
+        .unwrap();
+
+        handle_config_action_with_config(
+            ConfigAction::Set {
+                key: "preferred_browser".to_string(),
+                new: "".to_string(),
+            },
+            Some(&config_path),
+        )
+        .unwrap();
+
+        let config: OtotConfig = load_config(Some(&config_path)).unwrap();
+        assert_eq!(config.browser.preferred, None);
+    }
+    #[test]
+    fn config_get_unknown_key() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+
+        let result = handle_config_action_with_config(
+            ConfigAction::Get {
+                key: Some("nonexistent_key".to_string()),
+                json: false,
+            },
+            Some(&config_path),
+        );
+
+        assert!(result.is_err());
+        assert!(
+            result
+                .unwrap_err()
+                .to_string()
+                .contains("Unknown config key")
+        );
+    }
+    #[test]
+    fn config_set_unknown_key() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+
+        let result = handle_config_action_with_config(
+            ConfigAction::Set {
+                key: "nonexistent_key".to_string(),
+                new: "some_value".to_string(),
+            },
+            Some(&config_path),
+        );
+
+        assert!(result.is_err());
+        assert!(
+            result
+                .unwrap_err()
+                .to_string()
+                .contains("Unknown config key")
+        );
+    }
+    #[test]
+    fn config_set_slow_query_ms() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+
+        handle_config_action_with_config(
+            ConfigAction::Set {
+                key: "slow_query_ms".to_string(),
+                new: "250".to_string(),
+            },
+            Some(&config_path),
+        )
+        .unwrap();
+
+        let config: OtotConfig = load_config(Some(&config_path)).unwrap();
+        assert_eq!(config.database.slow_query_ms, Some(250));
+        assert_eq!(config.slow_query_threshold(), Duration::from_millis(250));
+    }
+    #[test]
+    fn config_set_slow_query_ms_rejects_non_numbers() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+
+        let result = handle_config_action_with_config(
+            ConfigAction::Set {
+                key: "slow_query_ms".to_string(),
+                new: "fast".to_string(),
+            },
+            Some(&config_path),
+        );
+
+        assert!(result.is_err());
+    }
+    #[test]
+    fn config_set_distractions_parses_comma_separated_list() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+
+        handle_config_action_with_config(
+            ConfigAction::Set {
+                key: "distractions".to_string(),
+                new: "reddit.com, news.ycombinator.com,".to_string(),
+            },
+            Some(&config_path),
+        )
+        .unwrap();
+
+        let config: OtotConfig = load_config(Some(&config_path)).unwrap();
+        assert_eq!(
+            config.focus.distractions,
+            vec!["reddit.com".to_string(), "news.ycombinator.com".to_string()]
+        );
+        assert_eq!(
+            config.get_value("distractions").unwrap().as_deref(),
+            Some("reddit.com,news.ycombinator.com")
+        );
+    }
+    #[test]
+    fn config_path_shows_custom_path() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+
+        let result = handle_config_action_with_config(ConfigAction::Path, Some(&config_path));
+
+        assert!(result.is_ok());
+    }
+    #[test]
+    fn config_get_when_file_does_not_exist_shows_not_set() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("nonexistent.toml");
+
+        let result = handle_config_action_with_config(
+            ConfigAction::Get {
+                key: Some("preferred_browser".to_string()),
+                json: false,
+            },
+            Some(&config_path),
+        );
+
+        assert!(result.is_ok());
+    }
+    #[test]
+    fn load_migrates_flat_v1_config_and_keeps_backup() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+        let v1 = "preferred_browser = \"firefox\"\nslow_query_ms = 250\ndistractions = [\"reddit.com\"]\n\n[audit]\nenabled = true\n";
+        std::fs::write(&config_path, v1).unwrap();
+
+        let config = load_config(Some(&config_path)).unwrap();
+
+        assert_eq!(config.version, CONFIG_VERSION);
+        assert_eq!(config.browser.preferred.as_deref(), Some("firefox"));
+        assert_eq!(config.database.slow_query_ms, Some(250));
+        assert_eq!(config.focus.distractions, vec!["reddit.com".to_string()]);
+        assert!(config.audit.enabled);
+
+        let rewritten = std::fs::read_to_string(&config_path).unwrap();
+        assert!(rewritten.contains("[browser]"));
+        assert!(!rewritten.contains("preferred_browser"));
+        let backup = temp_dir.path().join("config.toml.v1.bak");
+        assert_eq!(std::fs::read_to_string(&backup).unwrap(), v1);
+    }
+    #[cfg(unix)]
+    #[test]
+    fn config_backup_is_private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+        std::fs::write(&config_path, "preferred_browser = \"firefox\"\n").unwrap();
+
+        load_config(Some(&config_path)).unwrap();
+
+        let backup = temp_dir.path().join("config.toml.v1.bak");
+        let mode = std::fs::metadata(&backup).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+    #[test]
+    fn load_leaves_current_config_untouched() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+        store_config(Some(&config_path), &OtotConfig::default()).unwrap();
+
+        load_config(Some(&config_path)).unwrap();
+
+        assert!(!temp_dir.path().join("config.toml.v2.bak").exists());
+        assert!(!temp_dir.path().join("config.toml.v1.bak").exists());
+    }
+    #[test]
+    fn load_rejects_config_from_newer_version() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+        std::fs::write(&config_path, "version = 99\n").unwrap();
+
+        let result = load_config(Some(&config_path));
+
+        assert!(format!("{:#}", result.unwrap_err()).contains("newer otot"));
+    }
+    #[test]
+    fn config_set_viewer_adds_and_removes_template() {
+        let mut config = OtotConfig::default();
+
+        config
+            .set_value("viewers.archive", "https://archive.ph/{url}")
+            .unwrap();
+        assert_eq!(
+            config.get_value("viewers.archive").unwrap().as_deref(),
+            Some("https://archive.ph/{url}")
+        );
+
+        config.set_value("viewers.archive", "").unwrap();
+        assert_eq!(config.get_value("viewers.archive").unwrap(), None);
+        assert!(
+            config
+                .set_value("viewers.archive", "https://archive.ph/")
+                .is_err()
+        );
+    }
+    #[test]
+    fn config_set_matching_separators_validates_characters() {
+        let mut config = OtotConfig::default();
+
+        config.set_value("matching.separators", "/ .").unwrap();
+        assert_eq!(
+            config.get_value("matching.separators").unwrap().as_deref(),
+            Some("/ .")
+        );
+        assert!(config.set_value("matching.separators", "/x").is_err());
+        assert_eq!(config.separators(), Separators::parse(".").unwrap());
+    }
+    #[test]
+    fn saved_history_queries_round_trip_and_take_overrides() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+        let mut config = OtotConfig::default();
+        config.history.queries.insert(
+            "gh-week".to_string(),
+            HistoryQuery {
+                domain: Some("github.com".to_string()),
+                since: Some("7d".to_string()),
+                json: true,
+                ..Default::default()
+            },
+        );
+        store_config(Some(&config_path), &config).unwrap();
+
+        let loaded = load_config(Some(&config_path)).unwrap();
+        let query = &loaded.history.queries["gh-week"];
+        let merged = query.with_overrides(&HistoryQuery {
+            since: Some("1d".to_string()),
+            limit: Some(5),
+            ..Default::default()
+        });
+
+        assert_eq!(query, &config.history.queries["gh-week"]);
+        assert_eq!(merged.domain.as_deref(), Some("github.com"));
+        assert_eq!(merged.since.as_deref(), Some("1d"));
+        assert_eq!(merged.limit, Some(5));
+        assert!(merged.json);
+    }
+    #[test]
+    fn config_set_push_relay_validates_topic_url() {
+        let mut config = OtotConfig::default();
+
+        config
+            .set_value("push.relay", "https://ntfy.sh/otot-abc")
+            .unwrap();
+        assert_eq!(
+            config.push.relay.as_deref(),
+            Some("https://ntfy.sh/otot-abc")
+        );
+        assert!(config.set_value("push.relay", "not a url").is_err());
+    }
+    #[test]
+    fn limits_default_until_set_and_reject_non_numbers() {
+        let mut config = OtotConfig::default();
+        assert_eq!(config.limits.max_urls(), 50_000);
+        assert_eq!(config.get_value("limits.max_urls").unwrap(), None);
+
+        config.set_value("limits.max_urls", "0").unwrap();
+        config.set_value("limits.max_size", " 20MB ").unwrap();
+
+        assert_eq!(config.limits.max_urls(), 0);
+        assert_eq!(config.limits.max_size(), 20 << 20);
+        assert_eq!(
+            config.get_value("limits.max_size").unwrap().as_deref(),
+            Some("20MB")
+        );
+        assert!(config.set_value("limits.max_size", "20 parsecs").is_err());
+        assert!(
+            config
+                .set_value("limits.max_segment_candidates", "lots")
+                .is_err()
+        );
+        config.unset_value("limits.max_size").unwrap();
+        assert_eq!(config.limits.max_size(), 100 << 20);
+    }
+    #[test]
+    fn legacy_keys_map_to_sections() {
+        let mut config = OtotConfig::default();
+        config.set_value("preferred_browser", "brave").unwrap();
+
+        assert_eq!(config.browser.preferred.as_deref(), Some("brave"));
+        assert_eq!(
+            config.get_value("browser.preferred").unwrap().as_deref(),
+            Some("brave")
+        );
+    }
+    #[test]
+    fn unset_reverts_keys_to_defaults() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+        let mut config = OtotConfig::default();
+        config.set_value("browser.preferred", "firefox").unwrap();
+        config.set_value("audit.enabled", "true").unwrap();
+        config
+            .set_value("viewers.archive", "https://archive.ph/{url}")
+            .unwrap();
+        store_config(Some(&config_path), &config).unwrap();
+
+        for key in ["preferred_browser", "audit.enabled", "viewers.archive"] {
+            handle_config_action_with_config(
+                ConfigAction::Unset {
+                    key: key.to_string(),
+                },
+                Some(&config_path),
+            )
+            .unwrap();
+        }
+
+        let config = load_config(Some(&config_path)).unwrap();
+        assert_eq!(config.browser.preferred, None);
+        assert!(!config.audit.enabled);
+        assert!(config.viewers.is_empty());
+        assert!(OtotConfig::default().unset_value("nope").is_err());
+    }
+    #[test]
+    fn values_lists_every_key_and_configured_viewers() {
+        let mut config = OtotConfig::default();
+        config.set_value("push.relay", "https://ntfy.sh/t").unwrap();
+        config
+            .set_value("viewers.archive", "https://archive.ph/{url}")
+            .unwrap();
+        config
+            .set_value("classify.host_schemes.*.internal", "http")
+            .unwrap();
+        config.set_value("openers.ssh", "kitty ssh {url}").unwrap();
+        config.set_value("log.levels.database", "DEBUG").unwrap();
+
+        let values = config.values().unwrap();
+
+        assert_eq!(values.len(), CONFIG_KEYS.len());
+        assert!(values.contains(&(
+            "log.levels.otot::database".to_string(),
+            Some("debug".to_string())
+        )));
+        assert!(values.contains(&(
+            "push.relay".to_string(),
+            Some("https://ntfy.sh/t".to_string())
+        )));
+        assert!(values.contains(&(
+            "viewers.archive".to_string(),
+            Some("https://archive.ph/{url}".to_string())
+        )));
+        assert!(values.contains(&(
+            "classify.host_schemes.*.internal".to_string(),
+            Some("http".to_string())
+        )));
+        assert!(!values.iter().any(|(key, _)| key == "viewers.<name>"));
+    }
+    #[test]
+    fn typed_values_are_validated_and_printed_canonically() {
+        let mut config = OtotConfig::default();
+
+        config.set_value("audit.enabled", "Yes").unwrap();
+        config.set_value("database.slow_query_ms", "2s").unwrap();
+        config
+            .set_value("focus.distractions", " reddit.com , ,news.ycombinator.com")
+            .unwrap();
+
+        assert_eq!(
+            config.get_value("audit.enabled").unwrap().as_deref(),
+            Some("true")
+        );
+        assert_eq!(config.database.slow_query_ms, Some(2000));
+        assert_eq!(
+            config.get_value("slow_query_ms").unwrap().as_deref(),
+            Some("2000ms")
+        );
+        assert_eq!(
+            config.get_value("focus.distractions").unwrap().as_deref(),
+            Some("reddit.com,news.ycombinator.com")
+        );
+        let err = config
+            .set_value("database.slow_query_ms", "soon")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("expected a duration such as 250ms or 2s"));
+    }
+    #[test]
+    fn get_json_returns_typed_values() {
+        let mut config = OtotConfig::default();
+        config
+            .set_value("focus.distractions", "reddit.com")
+            .unwrap();
+        config
+            .set_value("viewers.archive", "https://archive.ph/{url}")
+            .unwrap();
+
+        assert_eq!(
+            config.get_json("audit.enabled").unwrap(),
+            serde_json::json!(false)
+        );
+        assert_eq!(
+            config.get_json("distractions").unwrap(),
+            serde_json::json!(["reddit.com"])
+        );
+        assert_eq!(
+            config.get_json("viewers").unwrap(),
+            serde_json::json!({"archive": "https://archive.ph/{url}"})
+        );
+        assert_eq!(
+            config.get_json("browser.preferred").unwrap(),
+            serde_json::Value::Null
+        );
+        assert!(config.get_json("nope").is_err());
+    }
+    #[test]
+    fn on_ambiguous_accepts_known_policies() {
+        let mut config = OtotConfig::default();
+        assert_eq!(
+            config
+                .get_value("matching.on_ambiguous")
+                .unwrap()
+                .as_deref(),
+            Some("first")
+        );
+
+        config.set_value("matching.on_ambiguous", "Fail").unwrap();
+
+        assert_eq!(config.matching.on_ambiguous, AmbiguityPolicy::Fail);
+        assert!(config.set_value("matching.on_ambiguous", "guess").is_err());
+    }
+
+    #[test]
+    fn schemes_are_lowercased_names() {
+        let mut config = OtotConfig::default();
+
+        config
+            .set_value("classify.schemes", "Slack, vscode")
+            .unwrap();
+
+        assert_eq!(config.classify.schemes, ["slack", "vscode"]);
+        assert!(config.set_value("classify.schemes", "slack://").is_err());
+    }
+
+    #[test]
+    fn host_schemes_are_set_per_host_pattern() {
+        let mut config = OtotConfig::default();
+
+        config
+            .set_value("classify.host_schemes.*.Internal", "http")
+            .unwrap();
+
+        assert_eq!(
+            config
+                .get_value("classify.host_schemes.*.internal")
+                .unwrap()
+                .as_deref(),
+            Some("http")
+        );
+        assert_eq!(
+            config.get_json("classify.host_schemes.*.internal").unwrap(),
+            serde_json::json!("http")
+        );
+        assert!(
+            config
+                .set_value("classify.host_schemes.wiki", "ftp")
+                .is_err()
+        );
+        config
+            .unset_value("classify.host_schemes.*.internal")
+            .unwrap();
+        assert!(config.classify.host_schemes.is_empty());
+    }
+
+    #[test]
+    fn openers_are_checked_and_allow_their_scheme() {
+        let mut config = OtotConfig::default();
+
+        config
+            .set_value("openers.VSCode", "code --open-url {url}")
+            .unwrap();
+        config
+            .set_value("openers.directory", "nautilus {path}")
+            .unwrap();
+
+        assert_eq!(
+            config.get_value("openers.vscode").unwrap().as_deref(),
+            Some("code --open-url {url}")
+        );
+        assert_eq!(config.classify_options().schemes, ["vscode"]);
+        assert!(config.set_value("openers.ssh", "kitty 'ssh {url}").is_err());
+        assert!(config.set_value("openers.not a scheme", "true").is_err());
+        config.unset_value("openers.vscode").unwrap();
+        assert_eq!(config.openers.keys().collect::<Vec<_>>(), ["directory"]);
+    }
+
+    #[test]
+    fn rank_accepts_known_strategies() {
+        let mut config = OtotConfig::default();
+        assert_eq!(
+            config.get_value("matching.rank").unwrap().as_deref(),
+            Some("combined")
+        );
+
+        config.set_value("matching.rank", "Recency").unwrap();
+
+        assert_eq!(config.matching.rank, RankStrategy::Recency);
+        assert!(config.set_value("matching.rank", "random").is_err());
+    }
+
+    #[test]
+    fn log_levels_are_set_per_subsystem_or_module() {
+        let mut config = OtotConfig::default();
+
+        config.set_value("log.levels.database", "Debug").unwrap();
+        config
+            .set_value("log.levels.otot::network", "warn")
+            .unwrap();
+
+        assert_eq!(
+            config.get_value("log.levels.otot::database").unwrap(),
+            Some("debug".to_string())
+        );
+        assert_eq!(
+            config.get_json("log.levels.database").unwrap(),
+            serde_json::json!("debug")
+        );
+        assert_eq!(
+            config.log.levels.keys().collect::<Vec<_>>(),
+            ["otot::database", "otot::network"]
+        );
+        assert!(config.set_value("log.levels.database", "loud").is_err());
+        config.unset_value("log.levels.database").unwrap();
+        assert_eq!(config.log.levels.len(), 1);
+    }
+}
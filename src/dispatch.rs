@@ -0,0 +1,172 @@
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use url::Url;
+
+use crate::shell::split_command_line;
+
+/// Key of the `openers` table used for `file://` URLs of directories rather than files.
+pub const DIRECTORY_OPENER: &str = "directory";
+
+/// Commands used when `openers` doesn't name one. macOS opens `ssh://` in Terminal by itself.
+#[cfg(target_os = "macos")]
+const DEFAULT_OPENERS: &[(&str, &str)] = &[];
+#[cfg(windows)]
+const DEFAULT_OPENERS: &[(&str, &str)] = &[("ssh", "cmd /C start ssh {url}")];
+#[cfg(all(unix, not(target_os = "macos")))]
+const DEFAULT_OPENERS: &[(&str, &str)] = &[("ssh", "x-terminal-emulator -e ssh {url}")];
+
+/// What a resolved URL is opened with.
+#[derive(Debug, PartialEq)]
+pub enum Launcher {
+    /// The preferred browser, or the default one
+    Browser,
+    /// Whatever the system registered for the scheme, e.g. VS Code for `vscode://`, or the
+    /// file manager for a directory
+    System,
+    /// A command, split into words with its placeholders filled in
+    Command(Vec<String>),
+}
+
+/// Picks how to open `url`: the command configured in `openers` for its scheme, or for
+/// `directory` when it is a local directory, then the built-in default. Web pages and
+/// files go to the browser, and anything else to the system's handler for its scheme.
+pub fn launcher_for(url: &str, openers: &BTreeMap<String, String>) -> Result<Launcher> {
+    let Ok(parsed) = Url::parse(url) else {
+        return Ok(Launcher::Browser);
+    };
+    let directory =
+        parsed.scheme() == "file" && parsed.to_file_path().is_ok_and(|path| path.is_dir());
+    let kind = if directory {
+        DIRECTORY_OPENER
+    } else {
+        parsed.scheme()
+    };
+
+    let template = openers.get(kind).map(String::as_str).or_else(|| {
+        DEFAULT_OPENERS
+            .iter()
+            .find(|(builtin, _)| *builtin == kind)
+            .map(|(_, template)| *template)
+    });
+    if let Some(template) = template {
+        return opener_command(template, &parsed).map(Launcher::Command);
+    }
+
+    Ok(match parsed.scheme() {
+        "http" | "https" | "file" if !directory => Launcher::Browser,
+        _ => Launcher::System,
+    })
+}
+
+/// Splits `template` into words and fills in `{url}`, `{host}` and `{path}` (the local
+/// path for a file URL) within each one, so a value never splits into several arguments.
+pub fn opener_command(template: &str, url: &Url) -> Result<Vec<String>> {
+    let words = split_command_line(template)?;
+    if words.is_empty() {
+        anyhow::bail!("Opener command is empty");
+    }
+    let path = match url.to_file_path() {
+        Ok(path) if url.scheme() == "file" => path.display().to_string(),
+        _ => url.path().to_string(),
+    };
+
+    Ok(words
+        .iter()
+        .map(|word| {
+            word.replace("{url}", url.as_str())
+                .replace("{host}", url.host_str().unwrap_or(""))
+                .replace("{path}", &path)
+        })
+        .collect())
+}
+
+/// Checks an opener template from the config, with a sample URL standing in.
+pub fn validate_opener(template: &str) -> Result<()> {
+    let sample = Url::parse("ssh://example.com/").expect("sample URL is valid");
+    opener_command(template, &sample)
+        .map(drop)
+        .with_context(|| format!("Invalid opener command '{}'", template))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_fs::TempDir;
+
+    #[test]
+    fn web_pages_go_to_the_browser_and_other_schemes_to_the_system() {
+        let none = BTreeMap::new();
+
+        assert_eq!(
+            launcher_for("https://example.com/", &none).unwrap(),
+            Launcher::Browser
+        );
+        assert_eq!(
+            launcher_for("file:///no/such/file.html", &none).unwrap(),
+            Launcher::Browser
+        );
+        assert_eq!(
+            launcher_for("vscode://file/home/me/notes.md", &none).unwrap(),
+            Launcher::System
+        );
+
+        let dir = TempDir::new().unwrap();
+        let dir_url = Url::from_file_path(dir.path()).unwrap();
+        assert_eq!(
+            launcher_for(dir_url.as_str(), &none).unwrap(),
+            Launcher::System
+        );
+    }
+
+    #[test]
+    fn configured_openers_take_precedence() {
+        let dir = TempDir::new().unwrap();
+        let dir_url = Url::from_file_path(dir.path()).unwrap();
+        let openers = BTreeMap::from([
+            ("directory".to_string(), "nautilus {path}".to_string()),
+            ("ssh".to_string(), "kitty ssh '{host}'".to_string()),
+        ]);
+
+        assert_eq!(
+            launcher_for(dir_url.as_str(), &openers).unwrap(),
+            Launcher::Command(vec![
+                "nautilus".to_string(),
+                dir.path().display().to_string()
+            ])
+        );
+        assert_eq!(
+            launcher_for("ssh://deploy@build.example.com:2222", &openers).unwrap(),
+            Launcher::Command(vec![
+                "kitty".to_string(),
+                "ssh".to_string(),
+                "build.example.com".to_string()
+            ])
+        );
+    }
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    #[test]
+    fn ssh_opens_a_terminal_by_default() {
+        assert_eq!(
+            launcher_for("ssh://build.example.com", &BTreeMap::new()).unwrap(),
+            Launcher::Command(vec![
+                "x-terminal-emulator".to_string(),
+                "-e".to_string(),
+                "ssh".to_string(),
+                "ssh://build.example.com".to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn placeholders_never_split_into_several_arguments() {
+        let url = Url::parse("file:///home/me/My%20Documents/").unwrap();
+
+        assert_eq!(
+            opener_command("open -R {path}", &url).unwrap(),
+            ["open", "-R", "/home/me/My Documents/"]
+        );
+        assert!(validate_opener("   ").is_err());
+        assert!(validate_opener("kitty 'ssh {url}").is_err());
+    }
+}
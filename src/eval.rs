@@ -0,0 +1,175 @@
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+use crate::database::Database;
+use crate::rank::RankStrategy;
+use crate::url_classify::{ClassifyOptions, InputType, classify_input_with};
+
+/// A pattern someone would type and the URL it should open.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct EvalQuery {
+    pub pattern: String,
+    pub expected: String,
+}
+
+/// A query whose expected URL wasn't the top match, with what came first instead.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EvalMiss {
+    pub query: EvalQuery,
+    pub got: Option<String>,
+}
+
+/// How well the matcher resolves a labeled set of queries.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EvalReport {
+    pub queries: usize,
+    pub top_1: usize,
+    pub top_3: usize,
+    pub misses: Vec<EvalMiss>,
+}
+
+impl EvalReport {
+    /// The share of queries whose expected URL is the match `open` would launch.
+    pub fn precision_at_1(&self) -> f64 {
+        self.share(self.top_1)
+    }
+
+    /// The share of queries whose expected URL is among the first three matches.
+    pub fn precision_at_3(&self) -> f64 {
+        self.share(self.top_3)
+    }
+
+    fn share(&self, hits: usize) -> f64 {
+        if self.queries == 0 {
+            0.0
+        } else {
+            hits as f64 / self.queries as f64
+        }
+    }
+}
+
+/// Reads queries from a JSON array of `{"pattern": ..., "expected": ...}` objects.
+pub fn load_queries(path: &Path) -> Result<Vec<EvalQuery>> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    serde_json::from_str(&text).with_context(|| {
+        format!(
+            "{} is not a JSON list of pattern and expected URL pairs",
+            path.display()
+        )
+    })
+}
+
+/// Ranks the matches of every query by `strategy` and counts where the expected URL lands.
+/// Queries that are full URLs or paths rather than history patterns count as misses.
+pub fn evaluate(
+    db: &dyn Database,
+    queries: &[EvalQuery],
+    options: &ClassifyOptions,
+    strategy: RankStrategy,
+) -> Result<EvalReport> {
+    let mut report = EvalReport {
+        queries: queries.len(),
+        ..EvalReport::default()
+    };
+
+    for query in queries {
+        let top: Vec<String> = match classify_input_with(&query.pattern, options) {
+            InputType::FuzzyPattern(segments) => db
+                .ranked_matches(&segments, strategy)?
+                .into_iter()
+                .take(3)
+                .map(|(url, ..)| url)
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        if top.first() == Some(&query.expected) {
+            report.top_1 += 1;
+        } else {
+            report.misses.push(EvalMiss {
+                query: query.clone(),
+                got: top.first().cloned(),
+            });
+        }
+        if top.contains(&query.expected) {
+            report.top_3 += 1;
+        }
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::SqliteDatabase;
+    use assert_fs::TempDir;
+    use std::time::{Duration, SystemTime};
+
+    #[test]
+    fn queries_are_scored_by_where_the_expected_url_ranks() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = SqliteDatabase::open_at(&temp_dir.path().join("test.db")).unwrap();
+        let long_ago = SystemTime::now() - Duration::from_secs(30 * 86400);
+        for _ in 0..5 {
+            db.add_visit("https://github.com/rust-lang/rust/issues", long_ago)
+                .unwrap();
+        }
+        db.add_visit(
+            "https://github.com/tokio-rs/tokio/issues",
+            SystemTime::now(),
+        )
+        .unwrap();
+
+        let queries_file = temp_dir.path().join("queries.json");
+        std::fs::write(
+            &queries_file,
+            r#"[
+                    {"pattern": "gh issues", "expected": "https://github.com/tokio-rs/tokio/issues"},
+                    {"pattern": "gh rust issues", "expected": "https://github.com/rust-lang/rust/issues"},
+                    {"pattern": "https://example.com/", "expected": "https://example.com/"}
+                ]"#,
+        )
+        .unwrap();
+        let queries = load_queries(&queries_file).unwrap();
+
+        let report = evaluate(
+            &db,
+            &queries,
+            &ClassifyOptions::default(),
+            RankStrategy::Score,
+        )
+        .unwrap();
+        assert_eq!(report.queries, 3);
+        assert_eq!(report.top_1, 1);
+        assert_eq!(report.top_3, 2);
+        assert_eq!(report.misses.len(), 2);
+        assert_eq!(
+            report.misses[0].got.as_deref(),
+            Some("https://github.com/rust-lang/rust/issues")
+        );
+        assert_eq!(report.misses[1].got, None);
+
+        let recency = evaluate(
+            &db,
+            &queries,
+            &ClassifyOptions::default(),
+            RankStrategy::Recency,
+        )
+        .unwrap();
+        assert!(recency.precision_at_1() > report.precision_at_1());
+    }
+
+    #[test]
+    fn malformed_query_files_are_rejected() {
+        let temp_dir = TempDir::new().unwrap();
+        let queries_file = temp_dir.path().join("queries.json");
+        std::fs::write(&queries_file, r#"{"gh": "https://github.com"}"#).unwrap();
+
+        let error = load_queries(&queries_file).unwrap_err();
+        assert!(error.to_string().contains("not a JSON list"));
+    }
+}
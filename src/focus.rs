@@ -0,0 +1,54 @@
+use anyhow::Result;
+
+use crate::config::FocusConfig;
+use crate::database::Database;
+use crate::units::format_duration_short;
+use crate::unix_now;
+use crate::url_classify::url_host_matches_any;
+
+/// Refuses `url` while a focus session is running and its host is on the distraction list,
+/// counting the attempt against the session. Every command that launches a URL goes
+/// through this, so none of them is a way around focus mode.
+pub fn ensure_not_distracting(db: &mut dyn Database, focus: &FocusConfig, url: &str) -> Result<()> {
+    if !url_host_matches_any(url, &focus.distractions) {
+        return Ok(());
+    }
+    let Some(session) = db.focus_summary()?.active else {
+        return Ok(());
+    };
+
+    db.record_focus_block(session.id, url)?;
+    anyhow::bail!(
+        "Focus mode is on for another {} and {} is on your distraction list. Run `otot focus stop` to end the session",
+        format_duration_short(session.ends_at - unix_now()),
+        url
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::SqliteDatabase;
+    use assert_fs::TempDir;
+    use std::time::Duration;
+
+    #[test]
+    fn distractions_are_refused_and_counted_only_during_a_session() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = SqliteDatabase::open_at(&temp_dir.path().join("test.db")).unwrap();
+        let focus = FocusConfig {
+            distractions: vec!["reddit.com".to_string()],
+        };
+
+        ensure_not_distracting(&mut db, &focus, "https://old.reddit.com/r/rust").unwrap();
+
+        db.start_focus(Duration::from_secs(600)).unwrap();
+        let err = ensure_not_distracting(&mut db, &focus, "https://old.reddit.com/r/rust")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("distraction list"));
+        ensure_not_distracting(&mut db, &focus, "https://github.com/").unwrap();
+
+        assert_eq!(db.focus_summary().unwrap().total_blocked, 1);
+    }
+}
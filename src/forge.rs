@@ -0,0 +1,318 @@
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use anyhow::Result;
+use log::debug;
+use url::Url;
+
+use crate::database::Database;
+
+/// How many of the most frecent URLs are searched for a repository when not inside one.
+const REPO_CANDIDATES: u16 = 200;
+
+/// The code hosts whose pull request and notification pages otot knows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Forge {
+    GitHub,
+    GitLab,
+}
+
+/// A repository on a forge, e.g. `rust-lang/rust` on `github.com`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Repo {
+    pub forge: Forge,
+    pub host: String,
+    pub path: String,
+}
+
+/// A developer destination typed as a word rather than a URL or history pattern.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shorthand {
+    /// `pr 1234`: a pull request, or merge request on GitLab
+    PullRequest(u64),
+    /// `notifications`
+    Notifications,
+}
+
+impl Shorthand {
+    /// Recognizes `pr 1234` (also `pr #1234` or `pr/1234`) and `notifications`.
+    pub fn parse(address: &str) -> Option<Self> {
+        let words: Vec<&str> = address
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .filter(|word| !word.is_empty())
+            .collect();
+        match words.as_slice() {
+            ["pr", number] => number
+                .trim_start_matches('#')
+                .parse()
+                .ok()
+                .map(Shorthand::PullRequest),
+            ["notifications"] => Some(Shorthand::Notifications),
+            _ => None,
+        }
+    }
+
+    /// The page this shorthand means on `repo`'s forge.
+    pub fn url(self, repo: &Repo) -> String {
+        match (self, repo.forge) {
+            (Shorthand::PullRequest(number), Forge::GitHub) => {
+                format!("https://{}/{}/pull/{}", repo.host, repo.path, number)
+            }
+            (Shorthand::PullRequest(number), Forge::GitLab) => format!(
+                "https://{}/{}/-/merge_requests/{}",
+                repo.host, repo.path, number
+            ),
+            (Shorthand::Notifications, Forge::GitHub) => {
+                format!("https://{}/notifications", repo.host)
+            }
+            (Shorthand::Notifications, Forge::GitLab) => {
+                format!("https://{}/dashboard/todos", repo.host)
+            }
+        }
+    }
+}
+
+fn forge_of(host: &str) -> Option<Forge> {
+    if host == "github.com" || host.starts_with("github.") {
+        Some(Forge::GitHub)
+    } else if host == "gitlab.com" || host.starts_with("gitlab.") {
+        Some(Forge::GitLab)
+    } else {
+        None
+    }
+}
+
+/// The repository a git remote points at, for `https://`, `ssh://` and scp-like
+/// `git@host:owner/repo.git` remotes on a known forge.
+pub fn parse_remote(remote: &str) -> Option<Repo> {
+    let remote = remote.trim();
+    let (host, path) = match Url::parse(remote) {
+        Ok(url) if url.has_host() => (url.host_str()?.to_string(), url.path().to_string()),
+        _ => {
+            let (user_host, path) = remote.split_once(':')?;
+            let host = user_host.rsplit('@').next()?;
+            (host.to_string(), path.to_string())
+        }
+    };
+    let path = path.trim_matches('/').trim_end_matches(".git");
+    if path.split('/').filter(|part| !part.is_empty()).count() < 2 {
+        return None;
+    }
+    Some(Repo {
+        forge: forge_of(&host)?,
+        host,
+        path: path.to_string(),
+    })
+}
+
+/// The repository a visited URL belongs to, e.g. `rust-lang/rust` for one of its issues.
+/// GitLab URLs are taken to end the project path at `/-/`, and otherwise at two segments.
+fn repo_of_url(url: &str) -> Option<Repo> {
+    let url = Url::parse(url).ok()?;
+    let host = url.host_str()?;
+    let forge = forge_of(host)?;
+    let segments: Vec<&str> = url.path_segments()?.filter(|s| !s.is_empty()).collect();
+    let path = match forge {
+        Forge::GitLab if segments.contains(&"-") => {
+            segments[..segments.iter().position(|s| *s == "-")?].join("/")
+        }
+        _ => segments.get(..2)?.join("/"),
+    };
+    parse_remote(&format!("https://{}/{}", host, path))
+}
+
+/// The repository of the `origin` remote of the git checkout `dir` is in, if any.
+pub fn current_repo(dir: &Path) -> Option<Repo> {
+    let output = Command::new("git")
+        .args(["remote", "get-url", "origin"])
+        .current_dir(dir)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .inspect_err(|e| debug!("Could not run git: {}", e))
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    parse_remote(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// The URL `address` stands for when it is a shorthand: on `repo`, the repository being
+/// worked in, or else on the most frecent repository in the history. Notifications fall
+/// back to GitHub when the history has no repositories either.
+pub fn resolve_shorthand(
+    db: &dyn Database,
+    address: &str,
+    repo: Option<Repo>,
+) -> Result<Option<String>> {
+    let Some(shorthand) = Shorthand::parse(address) else {
+        return Ok(None);
+    };
+
+    let repo = match repo {
+        Some(repo) => Some(repo),
+        None => db
+            .top_candidates(REPO_CANDIDATES)?
+            .iter()
+            .find_map(|entry| repo_of_url(&entry.url)),
+    };
+    let url = match (repo, shorthand) {
+        (Some(repo), _) => shorthand.url(&repo),
+        (None, Shorthand::Notifications) => shorthand.url(&Repo {
+            forge: Forge::GitHub,
+            host: "github.com".to_string(),
+            path: String::new(),
+        }),
+        (None, Shorthand::PullRequest(_)) => anyhow::bail!(
+            "Not in a git repository with a GitHub or GitLab remote, and no repository in \
+             your history to open {} of",
+            address.trim()
+        ),
+    };
+    debug!("Shorthand {:?} resolved to {}", address, url);
+    Ok(Some(url))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::SqliteDatabase;
+    use assert_fs::TempDir;
+    use std::time::{Duration, SystemTime};
+
+    fn github(path: &str) -> Repo {
+        Repo {
+            forge: Forge::GitHub,
+            host: "github.com".to_string(),
+            path: path.to_string(),
+        }
+    }
+
+    #[test]
+    fn shorthands_are_recognized() {
+        assert_eq!(
+            Shorthand::parse("pr 1234"),
+            Some(Shorthand::PullRequest(1234))
+        );
+        assert_eq!(Shorthand::parse("pr #7"), Some(Shorthand::PullRequest(7)));
+        assert_eq!(Shorthand::parse("pr/42"), Some(Shorthand::PullRequest(42)));
+        assert_eq!(
+            Shorthand::parse(" notifications "),
+            Some(Shorthand::Notifications)
+        );
+        assert_eq!(Shorthand::parse("pr review"), None);
+        assert_eq!(Shorthand::parse("gh pr 12"), None);
+        assert_eq!(Shorthand::parse("https://github.com/pr/1"), None);
+    }
+
+    #[test]
+    fn remotes_of_every_form_are_parsed() {
+        for remote in [
+            "git@github.com:rust-lang/rust.git",
+            "https://github.com/rust-lang/rust.git\n",
+            "ssh://git@github.com/rust-lang/rust",
+            "https://user@github.com/rust-lang/rust/",
+        ] {
+            assert_eq!(
+                parse_remote(remote),
+                Some(github("rust-lang/rust")),
+                "{}",
+                remote
+            );
+        }
+        assert_eq!(
+            parse_remote("git@gitlab.com:group/sub/project.git"),
+            Some(Repo {
+                forge: Forge::GitLab,
+                host: "gitlab.com".to_string(),
+                path: "group/sub/project".to_string(),
+            })
+        );
+        assert_eq!(parse_remote("git@example.com:team/app.git"), None);
+        assert_eq!(parse_remote("/srv/git/app.git"), None);
+    }
+
+    #[test]
+    fn current_repo_reads_the_origin_remote() {
+        let temp_dir = TempDir::new().unwrap();
+        let git = |args: &[&str]| {
+            Command::new("git")
+                .args(args)
+                .current_dir(temp_dir.path())
+                .output()
+                .unwrap()
+        };
+        assert_eq!(current_repo(temp_dir.path()), None);
+
+        git(&["init", "--quiet"]);
+        git(&[
+            "remote",
+            "add",
+            "origin",
+            "git@github.com:rust-lang/rust.git",
+        ]);
+        assert_eq!(
+            current_repo(temp_dir.path()),
+            Some(github("rust-lang/rust"))
+        );
+    }
+
+    #[test]
+    fn shorthands_link_to_the_forge_pages() {
+        let gitlab = parse_remote("https://gitlab.example.org/group/app").unwrap();
+
+        assert_eq!(
+            Shorthand::PullRequest(12).url(&github("rust-lang/rust")),
+            "https://github.com/rust-lang/rust/pull/12"
+        );
+        assert_eq!(
+            Shorthand::PullRequest(12).url(&gitlab),
+            "https://gitlab.example.org/group/app/-/merge_requests/12"
+        );
+        assert_eq!(
+            Shorthand::Notifications.url(&gitlab),
+            "https://gitlab.example.org/dashboard/todos"
+        );
+    }
+
+    #[test]
+    fn without_a_checkout_the_most_frecent_repo_is_used() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = SqliteDatabase::open_at(&temp_dir.path().join("test.db")).unwrap();
+        let now = SystemTime::now();
+        db.add_visit("https://docs.rs/tokio", now).unwrap();
+        db.add_visit("https://github.com/tokio-rs/tokio/issues/1", now)
+            .unwrap();
+        db.add_visit(
+            "https://github.com/rust-lang/rust",
+            now - Duration::from_secs(30 * 86400),
+        )
+        .unwrap();
+
+        assert_eq!(
+            resolve_shorthand(&db, "pr 5", None).unwrap().as_deref(),
+            Some("https://github.com/tokio-rs/tokio/pull/5")
+        );
+        assert_eq!(
+            resolve_shorthand(&db, "pr 5", Some(github("rust-lang/rust")))
+                .unwrap()
+                .as_deref(),
+            Some("https://github.com/rust-lang/rust/pull/5")
+        );
+        assert_eq!(resolve_shorthand(&db, "tokio issues", None).unwrap(), None);
+    }
+
+    #[test]
+    fn pull_requests_need_a_repo_but_notifications_do_not() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = SqliteDatabase::open_at(&temp_dir.path().join("test.db")).unwrap();
+
+        assert!(resolve_shorthand(&db, "pr 5", None).is_err());
+        assert_eq!(
+            resolve_shorthand(&db, "notifications", None)
+                .unwrap()
+                .as_deref(),
+            Some("https://github.com/notifications")
+        );
+    }
+}
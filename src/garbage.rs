@@ -0,0 +1,150 @@
+use url::Url;
+
+/// Path segments of sign-in handshakes, which only make sense mid-flow.
+const AUTH_SEGMENTS: &[&str] = &["callback", "oauth-callback", "oauth_callback", "authorize"];
+const AUTH_PARAMS: &[&str] = &["oauth_token", "oauth_verifier", "samlresponse", "id_token"];
+const LOGOUT_SEGMENTS: &[&str] = &[
+    "logout", "log-out", "log_out", "signout", "sign-out", "sign_out", "logoff",
+];
+/// Links that work once: password resets, email confirmations, magic sign-in links.
+const ONE_TIME_SEGMENTS: &[&str] = &[
+    "reset-password",
+    "reset_password",
+    "password-reset",
+    "magic-link",
+    "magic_link",
+    "verify-email",
+    "confirm-email",
+    "email-verification",
+    "unsubscribe",
+];
+const ONE_TIME_PARAMS: &[&str] = &[
+    "otp",
+    "magic",
+    "ticket",
+    "confirmation_token",
+    "reset_token",
+];
+const PAGE_PARAMS: &[&str] = &["page", "p", "pg"];
+const OFFSET_PARAMS: &[&str] = &["offset", "start", "skip"];
+/// Hosts and paths that only forward to another site, counting the click on the way.
+const REDIRECTORS: &[(&str, &str)] = &[
+    ("t.co", "/"),
+    ("l.facebook.com", "/l.php"),
+    ("lm.facebook.com", "/l.php"),
+    ("l.instagram.com", "/"),
+    ("out.reddit.com", "/"),
+    ("www.google.com", "/url"),
+    ("google.com", "/url"),
+    ("www.youtube.com", "/redirect"),
+    ("slack-redir.net", "/link"),
+];
+const REDIRECT_PARAMS: &[&str] = &["url", "u", "redirect", "redirect_url", "target", "dest"];
+
+/// Why `url` is likely not worth keeping in history, e.g. `a logout link`; `None` for an
+/// ordinary page. These URLs are left out of matching and can be pruned on their own.
+pub fn garbage_reason(url: &str) -> Option<&'static str> {
+    let parsed = Url::parse(url).ok()?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return None;
+    }
+    let host = parsed.host_str()?.to_ascii_lowercase();
+    let path = parsed.path().to_ascii_lowercase();
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    let params: Vec<(String, String)> = parsed
+        .query_pairs()
+        .map(|(name, value)| (name.to_ascii_lowercase(), value.into_owned()))
+        .collect();
+    let param = |names: &[&str]| {
+        params
+            .iter()
+            .find(|(name, value)| names.contains(&name.as_str()) && !value.is_empty())
+            .map(|(_, value)| value.as_str())
+    };
+    let has_segment = |names: &[&str]| segments.iter().any(|s| names.contains(s));
+
+    if (param(&["code"]).is_some() && param(&["state"]).is_some())
+        || param(AUTH_PARAMS).is_some()
+        || has_segment(AUTH_SEGMENTS)
+    {
+        return Some("an OAuth callback");
+    }
+    if has_segment(LOGOUT_SEGMENTS) {
+        return Some("a logout link");
+    }
+    if has_segment(ONE_TIME_SEGMENTS) || param(ONE_TIME_PARAMS).is_some() {
+        return Some("a one-time link");
+    }
+    if REDIRECTORS
+        .iter()
+        .any(|(redirector, prefix)| host == *redirector && path.starts_with(prefix))
+        || param(REDIRECT_PARAMS)
+            .is_some_and(|target| target.starts_with("https://") || target.starts_with("http://"))
+    {
+        return Some("a tracking redirect");
+    }
+
+    let number = |value: Option<&str>| value.and_then(|v| v.parse::<u64>().ok());
+    let later_page = number(param(PAGE_PARAMS)).is_some_and(|page| page > 1)
+        || number(param(OFFSET_PARAMS)).is_some_and(|offset| offset > 0)
+        || segments
+            .windows(2)
+            .any(|pair| pair[0] == "page" && number(Some(pair[1])).is_some_and(|page| page > 1));
+    if later_page {
+        return Some("a later page of a list");
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transient_urls_are_recognized() {
+        let cases = [
+            (
+                "https://app.example.com/auth/callback?code=abc&state=xyz",
+                "an OAuth callback",
+            ),
+            ("https://github.com/logout", "a logout link"),
+            (
+                "https://example.com/account/reset-password?id=42",
+                "a one-time link",
+            ),
+            ("https://t.co/AbCdEf123", "a tracking redirect"),
+            (
+                "https://www.google.com/url?q=https://docs.rs/",
+                "a tracking redirect",
+            ),
+            (
+                "https://news.example.com/go?url=https%3A%2F%2Fexample.org%2F",
+                "a tracking redirect",
+            ),
+            (
+                "https://forum.example.com/t/rust?page=4",
+                "a later page of a list",
+            ),
+            ("https://blog.example.com/page/3", "a later page of a list"),
+        ];
+
+        for (url, reason) in cases {
+            assert_eq!(garbage_reason(url), Some(reason), "{}", url);
+        }
+    }
+
+    #[test]
+    fn ordinary_pages_are_kept() {
+        for url in [
+            "https://github.com/rust-lang/rust/issues?page=1",
+            "https://docs.rs/serde/latest/serde/",
+            "https://example.com/search?q=callback",
+            "https://example.com/code?state=draft",
+            "https://blog.example.com/page/about",
+            "file:///home/me/logout",
+        ] {
+            assert_eq!(garbage_reason(url), None, "{}", url);
+        }
+    }
+}
@@ -0,0 +1,202 @@
+use anyhow::Result;
+use std::borrow::Cow;
+use url::{Host, Url};
+
+use crate::prompt::{ask_yes_no, interactive};
+
+/// Cyrillic and Greek letters that pass for Latin ones in most fonts, e.g. `аррӏе` for
+/// `apple`.
+const LATIN_LOOKALIKES: &str = "асԁеһіјӏорԛѕԝхуοικνρυχ";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Script {
+    Latin,
+    Greek,
+    Cyrillic,
+    Armenian,
+    Hebrew,
+    Arabic,
+    Thai,
+    Han,
+    Kana,
+    Hangul,
+    Other,
+}
+
+impl Script {
+    /// The script of a letter; `None` for digits, hyphens and combining marks, which every
+    /// script shares.
+    fn of(c: char) -> Option<Self> {
+        let script = match c {
+            '0'..='9' | '-' | '\u{300}'..='\u{36F}' => return None,
+            'a'..='z' | 'A'..='Z' => Self::Latin,
+            '\u{D7}' | '\u{F7}' => Self::Other,
+            '\u{C0}'..='\u{24F}' | '\u{1E00}'..='\u{1EFF}' => Self::Latin,
+            '\u{370}'..='\u{3FF}' | '\u{1F00}'..='\u{1FFF}' => Self::Greek,
+            '\u{400}'..='\u{52F}' | '\u{2DE0}'..='\u{2DFF}' | '\u{A640}'..='\u{A69F}' => {
+                Self::Cyrillic
+            }
+            '\u{530}'..='\u{58F}' => Self::Armenian,
+            '\u{590}'..='\u{5FF}' => Self::Hebrew,
+            '\u{600}'..='\u{6FF}' | '\u{750}'..='\u{77F}' => Self::Arabic,
+            '\u{E00}'..='\u{E7F}' => Self::Thai,
+            '\u{3400}'..='\u{4DBF}' | '\u{4E00}'..='\u{9FFF}' => Self::Han,
+            '\u{3040}'..='\u{30FF}' => Self::Kana,
+            '\u{1100}'..='\u{11FF}' | '\u{AC00}'..='\u{D7AF}' => Self::Hangul,
+            _ => Self::Other,
+        };
+        Some(script)
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Latin => "Latin",
+            Self::Greek => "Greek",
+            Self::Cyrillic => "Cyrillic",
+            Self::Armenian => "Armenian",
+            Self::Hebrew => "Hebrew",
+            Self::Arabic => "Arabic",
+            Self::Thai => "Thai",
+            Self::Han => "Han",
+            Self::Kana => "Kana",
+            Self::Hangul => "Hangul",
+            Self::Other => "other",
+        }
+    }
+
+    /// Japanese and Korean names mix Han with their own scripts, and with Latin, as a
+    /// matter of course.
+    fn mixes_naturally(self, other: Self) -> bool {
+        use Script::*;
+        matches!(
+            (self, other),
+            (Han, Kana | Hangul | Latin)
+                | (Kana | Hangul | Latin, Han)
+                | (Kana, Latin)
+                | (Latin, Kana)
+                | (Hangul, Latin)
+                | (Latin, Hangul)
+        )
+    }
+}
+
+/// `host` with its punycode labels decoded, e.g. `münchen.de` for `xn--mnchen-3ya.de`.
+/// A host that doesn't decode cleanly is returned as it is.
+pub fn unicode_host(host: &str) -> Cow<'_, str> {
+    if !host
+        .split('.')
+        .any(|label| label.to_ascii_lowercase().starts_with("xn--"))
+    {
+        return Cow::Borrowed(host);
+    }
+
+    match idna::domain_to_unicode(host) {
+        (unicode, Ok(())) => Cow::Owned(unicode),
+        (_, Err(_)) => Cow::Borrowed(host),
+    }
+}
+
+/// Why the host of `url` could be imitating another one with look-alike letters, e.g. a
+/// label that mixes Cyrillic and Latin letters; `None` for an ordinary host.
+pub fn homograph_risk(url: &str) -> Option<String> {
+    let parsed = Url::parse(url).ok()?;
+    let Some(Host::Domain(host)) = parsed.host() else {
+        return None;
+    };
+    let host = unicode_host(host);
+    let labels: Vec<&str> = host.split('.').filter(|label| !label.is_empty()).collect();
+    let tld_is_ascii = labels.last().is_some_and(|tld| tld.is_ascii());
+
+    labels.iter().find_map(|label| {
+        let mut scripts: Vec<Script> = Vec::new();
+        for script in label.chars().filter_map(Script::of) {
+            if !scripts.contains(&script) {
+                scripts.push(script);
+            }
+        }
+
+        if let Some((first, second)) = scripts.iter().enumerate().find_map(|(i, a)| {
+            scripts[i + 1..]
+                .iter()
+                .find(|b| !a.mixes_naturally(**b))
+                .map(|b| (*a, *b))
+        }) {
+            return Some(format!(
+                "'{}' mixes {} and {} letters",
+                label,
+                first.name(),
+                second.name()
+            ));
+        }
+
+        let imitates_latin = tld_is_ascii
+            && matches!(scripts.as_slice(), [Script::Cyrillic | Script::Greek])
+            && label
+                .chars()
+                .filter(|c| Script::of(*c).is_some())
+                .all(|c| LATIN_LOOKALIKES.contains(c));
+        imitates_latin.then(|| {
+            format!(
+                "'{}' is spelled in {} letters that look Latin",
+                label,
+                scripts[0].name()
+            )
+        })
+    })
+}
+
+/// Warns when the host of `url` could be imitating another with look-alike letters, and
+/// asks before opening it. Without a terminal to ask on, refuses.
+pub fn confirm_homograph(url: &str) -> Result<()> {
+    let Some(risk) = homograph_risk(url) else {
+        return Ok(());
+    };
+
+    if !interactive() {
+        anyhow::bail!(
+            "Refusing to open {}: {}, as phishing sites do to imitate others. Pass --no-homograph-check to open it anyway",
+            url,
+            risk
+        );
+    }
+
+    let question = format!(
+        "otot: {}: {}, as phishing sites do to imitate others. Open it anyway? [y/N] ",
+        url, risk
+    );
+    if !ask_yes_no(&question)? {
+        anyhow::bail!("Not opened");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn punycode_hosts_decode() {
+        assert_eq!(unicode_host("xn--mnchen-3ya.de"), "münchen.de");
+        assert_eq!(unicode_host("github.com"), "github.com");
+        assert_eq!(unicode_host("xn--.com"), "xn--.com");
+    }
+
+    #[test]
+    fn mixed_and_look_alike_scripts_are_risky() {
+        // Cyrillic `а` in an otherwise Latin name
+        let mixed = homograph_risk("https://\u{430}pple.com/").unwrap();
+        assert!(mixed.contains("mixes Cyrillic and Latin"), "{}", mixed);
+        // Every letter Cyrillic, but all of them look Latin
+        let lookalike = homograph_risk("https://xn--80ak6aa92e.com/").unwrap();
+        assert!(lookalike.contains("look Latin"), "{}", lookalike);
+    }
+
+    #[test]
+    fn ordinary_international_hosts_are_not_risky() {
+        assert_eq!(homograph_risk("https://münchen.de/"), None);
+        assert_eq!(homograph_risk("https://пример.рф/"), None);
+        assert_eq!(homograph_risk("https://東京タワー.jp/"), None);
+        assert_eq!(homograph_risk("https://github.com/rust-lang"), None);
+        assert_eq!(homograph_risk("http://[::1]:8080/"), None);
+    }
+}
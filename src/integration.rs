@@ -0,0 +1,113 @@
+use clap::ValueEnum;
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Editor {
+    Vim,
+    Emacs,
+    Vscode,
+}
+
+// The Vim and Emacs snippets resolve through `otot match --porcelain`, whose contract is:
+// exactly one line containing the resolved URL and exit status 0, or no output and a
+// non-zero exit status. VS Code can't capture output without a shell, which would run
+// whatever the selection says, so its task hands the selection to `otot open` as is.
+const VIM_SNIPPET: &str = r#"" otot: open the URL or pattern under the cursor with <leader>o, or :Otot <pattern>
+function! OtotOpen(pattern) abort
+  let l:url = trim(system('otot match --porcelain -- ' . shellescape(a:pattern)))
+  if v:shell_error
+    echohl WarningMsg | echo 'otot: no match for ' . a:pattern | echohl None
+    return
+  endif
+  call system('otot open -- ' . shellescape(l:url))
+  echo 'otot: opened ' . l:url
+endfunction
+command! -nargs=1 Otot call OtotOpen(<q-args>)
+nnoremap <silent> <leader>o :call OtotOpen(expand('<cWORD>'))<CR>
+"#;
+
+const EMACS_SNIPPET: &str = r#";; otot: open the URL or pattern at point with C-c o
+(defun otot-open-at-point ()
+  "Resolve the URL or otot pattern at point and open it through otot."
+  (interactive)
+  (let* ((pattern (or (thing-at-point 'url t)
+                      (thing-at-point 'symbol t)
+                      (read-string "otot pattern: ")))
+         (url (with-temp-buffer
+                (when (eq 0 (call-process "otot" nil t nil "match" "--porcelain" "--" pattern))
+                  (string-trim (buffer-string))))))
+    (if (not url)
+        (message "otot: no match for %s" pattern)
+      (call-process "otot" nil 0 nil "open" "--" url)
+      (message "otot: opened %s" url))))
+(global-set-key (kbd "C-c o") #'otot-open-at-point)
+"#;
+
+const VSCODE_SNIPPET: &str = r#"// otot: add to .vscode/tasks.json, then bind "workbench.action.tasks.runTask"
+// with args "otot: open selection" in keybindings.json
+{
+  "version": "2.0.0",
+  "tasks": [
+    {
+      "label": "otot: open selection",
+      "type": "process",
+      "command": "otot",
+      "args": ["open", "--", "${selectedText}"],
+      "presentation": { "reveal": "never" },
+      "problemMatcher": []
+    }
+  ]
+}
+"#;
+
+pub fn editor_snippet(editor: Editor) -> &'static str {
+    match editor {
+        Editor::Vim => VIM_SNIPPET,
+        Editor::Emacs => EMACS_SNIPPET,
+        Editor::Vscode => VSCODE_SNIPPET,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn terminal_editor_snippets_use_the_porcelain_contract() {
+        for editor in [Editor::Vim, Editor::Emacs] {
+            assert!(
+                editor_snippet(editor).contains("--porcelain"),
+                "{:?} snippet should resolve through match --porcelain",
+                editor
+            );
+        }
+    }
+
+    #[test]
+    fn snippets_separate_patterns_from_flags() {
+        for editor in [Editor::Vim, Editor::Emacs] {
+            let snippet = editor_snippet(editor);
+            assert!(
+                snippet.contains("--porcelain -- ") || snippet.contains(r#""--porcelain" "--""#)
+            );
+        }
+        assert!(editor_snippet(Editor::Vscode).contains(r#""--", "${selectedText}""#));
+    }
+
+    #[test]
+    fn vscode_passes_the_selection_without_a_shell() {
+        let snippet = editor_snippet(Editor::Vscode);
+        let json: String = snippet
+            .lines()
+            .filter(|line| !line.starts_with("//"))
+            .collect();
+        let tasks: serde_json::Value = serde_json::from_str(&json).unwrap();
+        let task = &tasks["tasks"][0];
+
+        assert_eq!(task["type"], "process");
+        assert_eq!(task["command"], "otot");
+        assert_eq!(
+            task["args"],
+            serde_json::json!(["open", "--", "${selectedText}"])
+        );
+    }
+}
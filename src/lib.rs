@@ -1,124 +1,108 @@
+mod audit;
 mod browser;
+mod config;
 mod database;
+mod dispatch;
+mod eval;
+mod focus;
+mod forge;
+mod garbage;
+mod idn;
+mod integration;
+mod limits;
+mod locale;
+mod network;
+mod notify;
+mod pack;
+mod paths;
+mod permissions;
+mod preview;
+mod prompt;
+mod prune;
+mod push;
+mod rank;
+mod redirects;
+mod rescore;
+mod sanitize;
+mod shell;
+mod state;
+mod synthetic;
+mod typo;
+mod units;
 mod url_classify;
-use std::time::{Duration, SystemTime};
-
-pub use browser::{BrowserOpener, SystemBrowserOpener, open_address_impl};
-pub use database::{Database, SqliteDatabase};
-pub use url_classify::{InputType, classify_input};
+mod viewers;
+mod wrap;
+use std::time::SystemTime;
+
+pub use audit::{AuditContext, AuditEntry, append_audit_entry, current_user};
+pub use browser::{
+    AMBIGUOUS_EXIT_CODE, AmbiguousMatch, BrowserOpener, LaunchFailed, SystemBrowserOpener,
+    explain_address, open_address_impl, open_address_with, ranked_match, resolve_address,
+    tied_matches,
+};
+pub use config::{
+    AmbiguityPolicy, AuditConfig, BrowserConfig, ClassifyConfig, ConfigAction, DatabaseConfig,
+    FocusConfig, HistoryConfig, HistoryQuery, LimitsConfig, LogConfig, MatchingConfig,
+    NetworkConfig, OtotConfig, PreviewConfig, PushConfig, handle_config_action,
+    handle_config_action_with_config, load_config, store_config, subsystem_module,
+};
+pub use database::{
+    DEFAULT_SLOW_QUERY_THRESHOLD, Database, DatabaseAnalysis, DatabaseCheck, DatabaseHealth,
+    DatabaseSummary, DomainStat, DomainSummary, FocusSession, FocusSummary, FrecencyBreakdown,
+    HistoryEntry, HistoryFilter, MergeFilter, MergePolicy, MergeSummary, Miss, PagePreview,
+    QueryTiming, RedirectCheck, Reminder, Resolution, SqliteDatabase, StatsPeriod, UrlAnnotations,
+    VisitSource, calculate_frecency, frecency_breakdown, is_unavailable,
+};
+pub use dispatch::{Launcher, launcher_for};
+pub use eval::{EvalMiss, EvalQuery, EvalReport, evaluate, load_queries};
+pub use focus::ensure_not_distracting;
+pub use forge::{Forge, Repo, Shorthand, current_repo, parse_remote, resolve_shorthand};
+pub use garbage::garbage_reason;
+pub use idn::{confirm_homograph, homograph_risk, unicode_host};
+pub use integration::{Editor, editor_snippet};
+pub use limits::{due_advisories, limit_advisories};
+pub use locale::{DateOrder, Locale};
+pub use network::{NetworkProbe, Reachability, TcpProbe, ensure_reachable};
+pub use notify::send_desktop_notification;
+pub use pack::{
+    Pack, PackChange, PackConflict, PackInstall, install_pack, load_pack, pack_changes,
+};
+pub use paths::{AppPaths, PORTABLE_FLAG_FILE};
+pub use preview::{
+    CurlFetcher, PageFetcher, describe_preview, extract_title, preview_pages, store_page_title,
+};
+pub use prompt::{ask_yes_no, interactive};
+pub use prune::{PruneWizardSummary, run_prune_wizard};
+pub use push::{new_relay_topic, print_qr_code, push_url};
+pub use rank::{
+    Candidate, CombinedRanker, FrecencyRanker, RankStrategy, Ranker, RecencyRanker, ScoreRanker,
+};
+pub use redirects::{CurlProbe, RedirectProbe, RedirectSummary, canonicalize_redirects};
+pub use rescore::{RescorePreview, preview_rescore, recent_patterns};
+pub use sanitize::{find_secrets, redact_secrets};
+pub use shell::{
+    background_hint, lossy_text, reconstruct_address, run_follow_up, running_in_background,
+    split_command_line, wildcard_hint,
+};
+pub use state::{StateImport, StatePart, export_state, import_state};
+pub use synthetic::HistoryGenerator;
+pub use typo::{confirm_typo_correction, suggest_typo_correction};
+pub use units::{format_duration, format_duration_short, format_size, parse_duration, parse_size};
+pub use url_classify::{
+    ClassifyOptions, InputType, Separators, canonical_address, check_scheme, classify_input,
+    classify_input_with, extract_urls, host_matches, known_suffix, url_host_matches_any,
+};
+pub use viewers::view_url;
+pub use wrap::{WrappedOutput, run_wrapped};
 
 use anyhow::{Context, Result};
-use clap::Subcommand;
-use log::info;
-use serde::{Deserialize, Serialize};
-
-#[derive(Debug, Default, Serialize, Deserialize)]
-pub struct OtotConfig {
-    pub preferred_browser: Option<String>,
-}
-
-#[derive(Subcommand, Debug)]
-pub enum ConfigAction {
-    Set {
-        #[arg(short, long)]
-        key: String,
-
-        #[arg(short, long)]
-        new: String,
-    },
-    Get {
-        #[arg(short, long)]
-        key: String,
-    },
-    Path,
-}
-
-pub fn handle_config_action(action: ConfigAction) -> Result<()> {
-    handle_config_action_with_config(action, None)
-}
-pub fn handle_config_action_with_config(
-    action: ConfigAction,
-    config_path: Option<&std::path::Path>,
-) -> Result<()> {
-    match action {
-        ConfigAction::Get { key } => {
-            let config: OtotConfig = if let Some(path) = config_path {
-                confy::load_path(path).context("Failed to load configuration")?
-            } else {
-                confy::load("otot", None).context("Failed to load configuration")?
-            };
-
-            match key.as_str() {
-                "preferred_browser" => match &config.preferred_browser {
-                    Some(browser) => println!("{}", browser),
-                    None => println!("(not set)"),
-                },
-                _ => {
-                    anyhow::bail!(
-                        "Unknown config key: '{}'. Valid keys: preferred_browser",
-                        key
-                    );
-                }
-            }
-
-            Ok(())
-        }
-
-        ConfigAction::Set { key, new } => {
-            let mut config: OtotConfig = if let Some(path) = config_path {
-                confy::load_path(path).unwrap_or_default()
-            } else {
-                confy::load("otot", None).context("Failed to load configuration")?
-            };
-
-            match key.as_str() {
-                "preferred_browser" => {
-                    let new_value = if new.is_empty() {
-                        None
-                    } else {
-                        Some(new.clone())
-                    };
 
-                    config.preferred_browser = new_value;
-
-                    if let Some(path) = config_path {
-                        confy::store_path(path, &config).context("Failed to save configuration")?;
-                    } else {
-                        confy::store("otot", None, &config)
-                            .context("Failed to save configuration")?;
-                    }
-
-                    info!(
-                        "Set preferred_browser to: {}",
-                        config.preferred_browser.as_deref().unwrap_or("(none)")
-                    );
-                    println!("Configuration updated");
-                }
-                _ => {
-                    anyhow::bail!(
-                        "Unknown config key: '{}'. Valid keys: preferred_browser",
-                        key
-                    );
-                }
-            }
-
-            Ok(())
-        }
-
-        ConfigAction::Path => {
-            let config_path_display = if let Some(path) = config_path {
-                path.display().to_string()
-            } else {
-                confy::get_configuration_file_path("otot", None)
-                    .context("Failed to get config path")?
-                    .display()
-                    .to_string()
-            };
-
-            println!("{}", config_path_display);
-            Ok(())
-        }
-    }
+/// The current time as a unix timestamp in seconds; 0 if the clock is set before 1970.
+pub fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64
 }
 
 pub fn format_relative_time(timestamp_secs: i64) -> String {
@@ -138,144 +122,97 @@ pub fn format_relative_time(timestamp_secs: i64) -> String {
     }
 }
 
-pub fn parse_duration(s: &str) -> Result<Duration> {
-    if s.is_empty() {
-        anyhow::bail!("Duration cannot be empty");
+/// Parses a `reopen` window into a half-open `[start, end)` range of unix timestamps.
+/// Accepts a duration reaching back from `now` (`3h`) or a clock range on a local day
+/// (`today 09:00-12:00`, `yesterday 14:00-18:30`, or just `09:00-12:00` for today).
+/// `utc_offset` is the local time zone's offset from UTC in seconds.
+pub fn parse_time_window(s: &str, now: i64, utc_offset: i64) -> Result<(i64, i64)> {
+    let s = s.trim();
+    if let Ok(duration) = parse_duration(s) {
+        return Ok((now - duration.as_secs() as i64, i64::MAX));
     }
 
-    let (num_str, unit) = s.split_at(s.len() - 1);
-    let num: u64 = num_str.parse().context("Invalid number in duration")?;
-
-    let seconds = match unit {
-        "d" => num * 86400,    // days
-        "w" => num * 604800,   // weeks
-        "m" => num * 2592000,  // months (30 days)
-        "y" => num * 31536000, // years (365 days)
-        _ => anyhow::bail!(
-            "Invalid duration unit. Use d (days), w (weeks), m (months), or y (years)"
-        ),
+    let (days_back, range) = match s.split_once(char::is_whitespace) {
+        Some(("today", range)) => (0, range.trim()),
+        Some(("yesterday", range)) => (1, range.trim()),
+        Some(_) => anyhow::bail!("Invalid window '{}'. Start it with today or yesterday", s),
+        None => (0, s),
     };
-
-    Ok(Duration::from_secs(seconds))
-}
-
-#[cfg(test)]
-mod config_tests {
-    use super::*;
-    use assert_fs::TempDir;
-    #[test]
-    fn config_set_and_get_preferred_browser() {
-        let temp_dir = TempDir::new().unwrap();
-        let config_path = temp_dir.path().join("config.toml");
-
-        handle_config_action_with_config(
-            ConfigAction::Set {
-                key: "preferred_browser".to_string(),
-                new: "firefox".to_string(),
-            },
-            Some(&config_path),
-        )
-        .unwrap();
-
-        let config: OtotConfig = confy::load_path(&config_path).unwrap();
-        assert_eq!(config.preferred_browser, Some("firefox".to_string()));
-
-        let result = handle_config_action_with_config(
-            ConfigAction::Get {
-                key: "preferred_browser".to_string(),
-            },
-            Some(&config_path),
+    let Some((start, end)) = range.split_once('-') else {
+        anyhow::bail!(
+            "Invalid window '{}'. Use a duration like 3h or a range like \"today 09:00-12:00\"",
+            s
         );
-        assert!(result.is_ok());
+    };
+    let (start, end) = (parse_clock_time(start)?, parse_clock_time(end)?);
+    if end <= start {
+        anyhow::bail!("Window '{}' ends before it starts", s);
     }
-    #[test]
-    fn config_set_empty_value_clears_setting() {
-        let temp_dir = TempDir::new().unwrap();
-        let config_path = temp_dir.path().join("config.toml");
 
-        handle_config_action_with_config(
-            ConfigAction::Set {
-                key: "preferred_browser".to_string(),
-                new: "firefox".to_string(),
-            },
-            Some(&config_path),
-        )
-        .unwrap();
-
-        handle_config_action_with_config(
-            ConfigAction::Set {
-                key: "preferred_browser".to_string(),
-                new: "".to_string(),
-            },
-            Some(&config_path),
-        )
-        .unwrap();
+    let midnight = (now + utc_offset).div_euclid(86400) * 86400 - days_back * 86400 - utc_offset;
+    Ok((midnight + start, midnight + end))
+}
 
-        let config: OtotConfig = confy::load_path(&config_path).unwrap();
-        assert_eq!(config.preferred_browser, None);
+/// Accepts a duration reaching back from `now` (`6m`) or a local date (`2023-01-01`, from
+/// its midnight) and returns the unix timestamp it starts at.
+pub fn parse_since(s: &str, now: i64, utc_offset: i64) -> Result<i64> {
+    let s = s.trim();
+    if let Ok(duration) = parse_duration(s) {
+        return Ok(now - duration.as_secs() as i64);
     }
-    #[test]
-    fn config_get_unknown_key() {
-        let temp_dir = TempDir::new().unwrap();
-        let config_path = temp_dir.path().join("config.toml");
-
-        let result = handle_config_action_with_config(
-            ConfigAction::Get {
-                key: "nonexistent_key".to_string(),
-            },
-            Some(&config_path),
-        );
 
-        assert!(result.is_err());
-        assert!(
-            result
-                .unwrap_err()
-                .to_string()
-                .contains("Unknown config key")
-        );
+    let invalid = || {
+        anyhow::anyhow!(
+            "Invalid start '{}'. Use a duration like 6m or a date like 2023-01-01",
+            s
+        )
+    };
+    let mut parts = s.splitn(3, '-').map(|part| part.parse::<i64>());
+    let (Some(Ok(year)), Some(Ok(month)), Some(Ok(day))) =
+        (parts.next(), parts.next(), parts.next())
+    else {
+        return Err(invalid());
+    };
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return Err(invalid());
     }
-    #[test]
-    fn config_set_unknown_key() {
-        let temp_dir = TempDir::new().unwrap();
-        let config_path = temp_dir.path().join("config.toml");
-
-        let result = handle_config_action_with_config(
-            ConfigAction::Set {
-                key: "nonexistent_key".to_string(),
-                new: "some_value".to_string(),
-            },
-            Some(&config_path),
-        );
 
-        assert!(result.is_err());
-        assert!(
-            result
-                .unwrap_err()
-                .to_string()
-                .contains("Unknown config key")
-        );
-    }
-    #[test]
-    fn config_path_shows_custom_path() {
-        let temp_dir = TempDir::new().unwrap();
-        let config_path = temp_dir.path().join("config.toml");
+    // Days since 1970-01-01 in the proleptic Gregorian calendar
+    let (y, m) = if month <= 2 {
+        (year - 1, month + 9)
+    } else {
+        (year, month - 3)
+    };
+    let era = y.div_euclid(400);
+    let year_of_era = y - era * 400;
+    let day_of_year = (153 * m + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146097 + day_of_era - 719468;
 
-        let result = handle_config_action_with_config(ConfigAction::Path, Some(&config_path));
+    Ok(days * 86400 - utc_offset)
+}
 
-        assert!(result.is_ok());
+/// Seconds since midnight for `HH:MM`; `24:00` is allowed as the end of a day.
+fn parse_clock_time(s: &str) -> Result<i64> {
+    let invalid = || anyhow::anyhow!("Invalid time '{}'. Use HH:MM, e.g. 09:30", s.trim());
+    let (hours, minutes) = s.trim().split_once(':').ok_or_else(invalid)?;
+    let hours: i64 = hours.parse().map_err(|_| invalid())?;
+    let minutes: i64 = minutes.parse().map_err(|_| invalid())?;
+    if minutes >= 60 || hours > 24 || (hours == 24 && minutes > 0) {
+        return Err(invalid());
     }
-    #[test]
-    fn config_get_when_file_does_not_exist_shows_not_set() {
-        let temp_dir = TempDir::new().unwrap();
-        let config_path = temp_dir.path().join("nonexistent.toml");
-
-        let result = handle_config_action_with_config(
-            ConfigAction::Get {
-                key: "preferred_browser".to_string(),
-            },
-            Some(&config_path),
-        );
+    Ok(hours * 3600 + minutes * 60)
+}
 
-        assert!(result.is_ok());
-    }
+/// The local time zone's current offset from UTC in seconds, as the C library sees it.
+pub fn local_utc_offset() -> Result<i64> {
+    // SQLite already knows how to ask the platform for local time, so borrow that
+    let conn = rusqlite::Connection::open_in_memory()?;
+    conn.query_row(
+        "SELECT CAST(strftime('%s', 'now', 'localtime') AS INTEGER)
+              - CAST(strftime('%s', 'now') AS INTEGER)",
+        [],
+        |row| row.get(0),
+    )
+    .context("Failed to determine the local time zone offset")
 }
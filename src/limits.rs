@@ -0,0 +1,124 @@
+use anyhow::Result;
+
+use crate::config::LimitsConfig;
+use crate::database::{Database, DatabaseHealth};
+use crate::units::format_size;
+
+/// Advisories are repeated at most this often, however long the limits stay exceeded.
+const ADVISORY_INTERVAL_SECS: i64 = 7 * 86400;
+const ADVISED_AT_KEY: &str = "limits.advised_at";
+
+/// What to do about each limit in `limits` that `health` exceeds, with the command to run.
+pub fn limit_advisories(health: &DatabaseHealth, limits: &LimitsConfig) -> Vec<String> {
+    let exceeds = |value: i64, limit: u64| limit > 0 && value > limit as i64;
+    let mut advisories = Vec::new();
+
+    if exceeds(health.urls, limits.max_urls()) {
+        advisories.push(format!(
+            "History holds {} URLs (limit {}); drop the ones not visited in a year with `otot prune --older-than 1y`",
+            health.urls,
+            limits.max_urls()
+        ));
+    }
+    if exceeds(health.size_bytes, limits.max_size()) {
+        advisories.push(format!(
+            "The history database is {} (limit {}); archive whole domains you no longer need with `otot prune --interactive`",
+            format_size(health.size_bytes as u64),
+            format_size(limits.max_size())
+        ));
+    }
+    if let Some((segment, candidates)) = &health.busiest_last_segment
+        && exceeds(*candidates, limits.max_segment_candidates())
+    {
+        advisories.push(format!(
+            "Patterns ending in '{}' score {} candidates each (limit {}); see which URLs end in it with `otot prune --dry-run --url '{}$'` and prune the unused ones",
+            segment,
+            candidates,
+            limits.max_segment_candidates(),
+            segment
+        ));
+    }
+
+    advisories
+}
+
+/// The advisories to show now: none if some were shown within the last week, otherwise
+/// those for the limits currently exceeded, which are then recorded as shown.
+pub fn due_advisories(
+    db: &mut dyn Database,
+    limits: &LimitsConfig,
+    now: i64,
+) -> Result<Vec<String>> {
+    let advised_at = db
+        .metadata(ADVISED_AT_KEY)?
+        .and_then(|value| value.parse::<i64>().ok());
+    if advised_at.is_some_and(|at| now - at < ADVISORY_INTERVAL_SECS) {
+        return Ok(Vec::new());
+    }
+
+    let advisories = limit_advisories(&db.health()?, limits);
+    if !advisories.is_empty() {
+        db.set_metadata(ADVISED_AT_KEY, &now.to_string())?;
+    }
+    Ok(advisories)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::SqliteDatabase;
+    use assert_fs::TempDir;
+    use std::time::SystemTime;
+
+    fn health(urls: i64, size_mb: i64, candidates: i64) -> DatabaseHealth {
+        DatabaseHealth {
+            urls,
+            size_bytes: size_mb * 1024 * 1024,
+            busiest_last_segment: Some(("issues".to_string(), candidates)),
+        }
+    }
+
+    #[test]
+    fn each_exceeded_limit_gets_a_command() {
+        let advisories = limit_advisories(&health(60_000, 150, 800), &LimitsConfig::default());
+
+        assert_eq!(advisories.len(), 3);
+        assert!(advisories[0].contains("otot prune --older-than 1y"));
+        assert!(advisories[1].contains("otot prune --interactive"));
+        assert!(advisories[2].contains("otot prune --dry-run --url 'issues$'"));
+        assert!(limit_advisories(&health(10, 1, 2), &LimitsConfig::default()).is_empty());
+    }
+
+    #[test]
+    fn zero_turns_a_limit_off() {
+        let limits = LimitsConfig {
+            max_urls: Some(0),
+            ..Default::default()
+        };
+
+        assert!(limit_advisories(&health(60_000, 1, 2), &limits).is_empty());
+    }
+
+    #[test]
+    fn advisories_are_shown_at_most_weekly() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = SqliteDatabase::open_at(&temp_dir.path().join("test.db")).unwrap();
+        db.add_visit("https://example.com/a", SystemTime::now())
+            .unwrap();
+        db.add_visit("https://example.com/b", SystemTime::now())
+            .unwrap();
+        let limits = LimitsConfig {
+            max_urls: Some(1),
+            ..Default::default()
+        };
+
+        assert_eq!(due_advisories(&mut db, &limits, 1000).unwrap().len(), 1);
+        assert!(due_advisories(&mut db, &limits, 2000).unwrap().is_empty());
+        assert_eq!(
+            due_advisories(&mut db, &limits, 1000 + ADVISORY_INTERVAL_SECS)
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+}
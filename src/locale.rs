@@ -0,0 +1,240 @@
+use anyhow::Result;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DateOrder {
+    YearMonthDay,
+    DayMonthYear,
+    MonthDayYear,
+}
+
+/// Number and date conventions for report output. Only the handful of rules reports need
+/// are modelled; unknown locales fall back to plain ISO-style output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Locale {
+    pub tag: String,
+    pub group_separator: Option<char>,
+    pub decimal_separator: char,
+    pub date_order: DateOrder,
+    pub date_separator: char,
+}
+
+impl Default for Locale {
+    fn default() -> Self {
+        Self {
+            tag: "C".to_string(),
+            group_separator: None,
+            decimal_separator: '.',
+            date_order: DateOrder::YearMonthDay,
+            date_separator: '-',
+        }
+    }
+}
+
+const NBSP: char = '\u{a0}';
+
+impl Locale {
+    /// Parses tags like `de-DE`, `en_GB.UTF-8` or `fr`. Returns `None` for languages
+    /// without known conventions.
+    pub fn parse(tag: &str) -> Option<Self> {
+        let base = tag.split(['.', '@']).next().unwrap_or_default();
+        if base.is_empty() || base == "C" || base == "POSIX" {
+            return Some(Self::default());
+        }
+
+        let mut parts = base.split(['-', '_']);
+        let language = parts.next()?.to_ascii_lowercase();
+        let region = parts
+            .next()
+            .map(str::to_ascii_uppercase)
+            .unwrap_or_default();
+
+        use DateOrder::*;
+        let (group, decimal, order, date_sep) = match (language.as_str(), region.as_str()) {
+            ("en", "US" | "PH") => (',', '.', MonthDayYear, '/'),
+            ("en", "GB" | "IE" | "AU" | "NZ" | "IN" | "ZA") => (',', '.', DayMonthYear, '/'),
+            ("en", _) => (',', '.', YearMonthDay, '-'),
+            ("de", "CH") => ('\'', '.', DayMonthYear, '.'),
+            ("de" | "da" | "nb" | "no" | "tr", _) => ('.', ',', DayMonthYear, '.'),
+            ("nl", _) => ('.', ',', DayMonthYear, '-'),
+            ("es" | "it" | "pt" | "el" | "id", _) => ('.', ',', DayMonthYear, '/'),
+            ("fr", "CA") => (NBSP, ',', YearMonthDay, '-'),
+            ("fr", _) => (NBSP, ',', DayMonthYear, '/'),
+            ("ru" | "uk" | "pl" | "cs" | "sk" | "fi", _) => (NBSP, ',', DayMonthYear, '.'),
+            ("sv" | "lt", _) => (NBSP, ',', YearMonthDay, '-'),
+            ("ja" | "zh" | "ko", _) => (',', '.', YearMonthDay, '/'),
+            _ => return None,
+        };
+
+        Some(Self {
+            tag: base.to_string(),
+            group_separator: Some(group),
+            decimal_separator: decimal,
+            date_order: order,
+            date_separator: date_sep,
+        })
+    }
+
+    /// An explicit `--locale` wins and must be known; otherwise the environment decides
+    /// (`LC_ALL`, `LC_NUMERIC`, then `LANG`), falling back to the default.
+    pub fn resolve(explicit: Option<&str>) -> Result<Self> {
+        if let Some(tag) = explicit {
+            return Self::parse(tag).ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Unsupported locale '{}'. Use a tag like en-US, de-DE or fr-FR, or C for plain output",
+                    tag
+                )
+            });
+        }
+
+        let from_env = ["LC_ALL", "LC_NUMERIC", "LANG"]
+            .iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|value| !value.is_empty());
+
+        Ok(from_env
+            .and_then(|tag| Self::parse(&tag))
+            .unwrap_or_default())
+    }
+
+    /// Formats an integer count with thousands separators, e.g. `12,345`.
+    pub fn count(&self, value: impl Into<i128>) -> String {
+        let value = value.into();
+        let digits = group_digits(&value.unsigned_abs().to_string(), self.group_separator);
+        if value < 0 {
+            format!("-{}", digits)
+        } else {
+            digits
+        }
+    }
+
+    /// Formats a number with a fixed number of decimal places.
+    pub fn decimal(&self, value: f64, places: usize) -> String {
+        let formatted = format!("{:.*}", places, value.abs());
+        let (integer, fraction) = formatted.split_once('.').unwrap_or((&formatted, ""));
+
+        let mut out = String::new();
+        if value.is_sign_negative() && formatted.chars().any(|c| c.is_ascii_digit() && c != '0') {
+            out.push('-');
+        }
+        out.push_str(&group_digits(integer, self.group_separator));
+        if !fraction.is_empty() {
+            out.push(self.decimal_separator);
+            out.push_str(fraction);
+        }
+        out
+    }
+
+    /// Formats the calendar date (UTC) of a unix timestamp.
+    pub fn date(&self, timestamp_secs: i64) -> String {
+        let (year, month, day) = civil_from_days(timestamp_secs.div_euclid(86400));
+        let sep = self.date_separator;
+        match self.date_order {
+            DateOrder::YearMonthDay => format!("{year}{sep}{month:02}{sep}{day:02}"),
+            DateOrder::DayMonthYear => format!("{day:02}{sep}{month:02}{sep}{year}"),
+            DateOrder::MonthDayYear => format!("{month:02}{sep}{day:02}{sep}{year}"),
+        }
+    }
+}
+
+fn group_digits(digits: &str, separator: Option<char>) -> String {
+    let Some(separator) = separator else {
+        return digits.to_string();
+    };
+
+    let mut out = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(separator);
+        }
+        out.push(digit);
+    }
+    out
+}
+
+// Howard Hinnant's days-to-civil algorithm
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2024-03-05 00:00:00 UTC
+    const MARCH_5_2024: i64 = 1_709_596_800;
+
+    #[test]
+    fn default_locale_is_plain() {
+        let locale = Locale::default();
+
+        assert_eq!(locale.count(1_234_567u32), "1234567");
+        assert_eq!(locale.decimal(1234.56, 1), "1234.6");
+        assert_eq!(locale.date(MARCH_5_2024), "2024-03-05");
+    }
+
+    #[test]
+    fn us_english_groups_with_commas_and_puts_month_first() {
+        let locale = Locale::parse("en_US.UTF-8").unwrap();
+
+        assert_eq!(locale.count(1_234_567u32), "1,234,567");
+        assert_eq!(locale.decimal(1234.5, 2), "1,234.50");
+        assert_eq!(locale.date(MARCH_5_2024), "03/05/2024");
+    }
+
+    #[test]
+    fn german_swaps_separators_and_puts_day_first() {
+        let locale = Locale::parse("de-DE").unwrap();
+
+        assert_eq!(locale.count(1_000u32), "1.000");
+        assert_eq!(locale.decimal(1234.5, 1), "1.234,5");
+        assert_eq!(locale.date(MARCH_5_2024), "05.03.2024");
+    }
+
+    #[test]
+    fn french_groups_with_no_break_space() {
+        let locale = Locale::parse("fr_FR").unwrap();
+
+        assert_eq!(locale.count(12_345u32), "12\u{a0}345");
+        assert_eq!(locale.date(MARCH_5_2024), "05/03/2024");
+    }
+
+    #[test]
+    fn small_and_negative_numbers() {
+        let locale = Locale::parse("en-GB").unwrap();
+
+        assert_eq!(locale.count(999u32), "999");
+        assert_eq!(locale.count(-1_500i64), "-1,500");
+        assert_eq!(locale.decimal(-0.01, 1), "0.0");
+        assert_eq!(locale.decimal(-2.5, 1), "-2.5");
+    }
+
+    #[test]
+    fn dates_before_epoch_and_leap_days() {
+        let locale = Locale::default();
+
+        assert_eq!(locale.date(-86_400), "1969-12-31");
+        assert_eq!(locale.date(951_782_400), "2000-02-29");
+    }
+
+    #[test]
+    fn explicit_unknown_locale_is_rejected() {
+        let err = Locale::resolve(Some("tlh-KLINGON")).unwrap_err();
+
+        assert!(err.to_string().contains("Unsupported locale"));
+    }
+
+    #[test]
+    fn unknown_languages_parse_to_none() {
+        assert_eq!(Locale::parse("xx_YY"), None);
+        assert_eq!(Locale::parse("C.UTF-8"), Some(Locale::default()));
+    }
+}
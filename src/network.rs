@@ -0,0 +1,170 @@
+use anyhow::Result;
+use log::debug;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+use url::Url;
+
+use crate::url_classify::host_matches;
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reachability {
+    Reachable,
+    /// The host name did not resolve, typically because only the internal resolver knows it
+    Unresolvable,
+    Unreachable,
+}
+
+pub trait NetworkProbe {
+    fn probe(&self, host: &str, port: u16) -> Reachability;
+}
+
+/// Resolves the host with the system resolver and tries a TCP connection to it.
+pub struct TcpProbe;
+impl NetworkProbe for TcpProbe {
+    fn probe(&self, host: &str, port: u16) -> Reachability {
+        let addrs = match (host, port).to_socket_addrs() {
+            Ok(addrs) => addrs.collect::<Vec<_>>(),
+            Err(e) => {
+                debug!("Could not resolve {}: {}", host, e);
+                return Reachability::Unresolvable;
+            }
+        };
+
+        let connected = addrs.iter().any(|addr| {
+            TcpStream::connect_timeout(addr, PROBE_TIMEOUT)
+                .inspect_err(|e| debug!("Could not connect to {}: {}", addr, e))
+                .is_ok()
+        });
+        match (addrs.is_empty(), connected) {
+            (true, _) => Reachability::Unresolvable,
+            (false, true) => Reachability::Reachable,
+            (false, false) => Reachability::Unreachable,
+        }
+    }
+}
+
+/// Fails with a "connect to the VPN first" error when `url`'s host matches one of
+/// `requires_vpn` and cannot be reached. Other hosts are not probed.
+pub fn ensure_reachable(
+    probe: &dyn NetworkProbe,
+    requires_vpn: &[String],
+    url: &str,
+) -> Result<()> {
+    let Ok(parsed) = Url::parse(url) else {
+        return Ok(());
+    };
+    let (Some(host), Some(port)) = (parsed.host_str(), parsed.port_or_known_default()) else {
+        return Ok(());
+    };
+    if !requires_vpn
+        .iter()
+        .any(|pattern| host_matches(host, pattern))
+    {
+        return Ok(());
+    }
+
+    match probe.probe(host, port) {
+        Reachability::Reachable => Ok(()),
+        Reachability::Unresolvable => anyhow::bail!(
+            "{} does not resolve; connect to the VPN first (it is listed in network.requires_vpn)",
+            host
+        ),
+        Reachability::Unreachable => anyhow::bail!(
+            "{} is not reachable on port {}; connect to the VPN first (it is listed in network.requires_vpn)",
+            host,
+            port
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::net::TcpListener;
+
+    struct FixedProbe {
+        result: Reachability,
+        probed: RefCell<Vec<(String, u16)>>,
+    }
+
+    impl FixedProbe {
+        fn new(result: Reachability) -> Self {
+            Self {
+                result,
+                probed: RefCell::new(Vec::new()),
+            }
+        }
+    }
+
+    impl NetworkProbe for FixedProbe {
+        fn probe(&self, host: &str, port: u16) -> Reachability {
+            self.probed.borrow_mut().push((host.to_string(), port));
+            self.result
+        }
+    }
+
+    fn corp() -> Vec<String> {
+        vec!["*.corp.example.com".to_string()]
+    }
+
+    #[test]
+    fn hosts_outside_the_list_are_not_probed() {
+        let probe = FixedProbe::new(Reachability::Unreachable);
+
+        ensure_reachable(&probe, &corp(), "https://github.com/").unwrap();
+
+        assert!(probe.probed.borrow().is_empty());
+    }
+
+    #[test]
+    fn unreachable_listed_host_asks_for_vpn() {
+        let probe = FixedProbe::new(Reachability::Unreachable);
+
+        let err = ensure_reachable(&probe, &corp(), "https://wiki.corp.example.com/page")
+            .unwrap_err()
+            .to_string();
+
+        assert!(err.contains("connect to the VPN first"));
+        assert_eq!(
+            *probe.probed.borrow(),
+            vec![("wiki.corp.example.com".to_string(), 443)]
+        );
+    }
+
+    #[test]
+    fn unresolvable_listed_host_asks_for_vpn() {
+        let probe = FixedProbe::new(Reachability::Unresolvable);
+
+        let err = ensure_reachable(&probe, &corp(), "http://jira.corp.example.com:8080/")
+            .unwrap_err()
+            .to_string();
+
+        assert!(err.contains("does not resolve"));
+    }
+
+    #[test]
+    fn reachable_listed_host_passes() {
+        let probe = FixedProbe::new(Reachability::Reachable);
+
+        assert!(ensure_reachable(&probe, &corp(), "https://wiki.corp.example.com/").is_ok());
+    }
+
+    #[test]
+    fn tcp_probe_distinguishes_open_and_closed_ports() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let open = listener.local_addr().unwrap().port();
+        let closed = {
+            let temporary = TcpListener::bind("127.0.0.1:0").unwrap();
+            temporary.local_addr().unwrap().port()
+        };
+
+        assert_eq!(TcpProbe.probe("127.0.0.1", open), Reachability::Reachable);
+        assert_eq!(
+            TcpProbe.probe("127.0.0.1", closed),
+            Reachability::Unreachable
+        );
+    }
+}
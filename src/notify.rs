@@ -0,0 +1,64 @@
+use anyhow::{Context, Result};
+use log::debug;
+use std::process::{Command, Stdio};
+
+/// Shows a desktop notification using the platform's stock tooling (`osascript` on macOS,
+/// `notify-send` elsewhere on Unix).
+pub fn send_desktop_notification(title: &str, body: &str) -> Result<()> {
+    let mut command = notification_command(title, body)?;
+    debug!("Sending desktop notification: {:?}", command);
+
+    let status = command
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .context("Failed to run the desktop notification command")?;
+
+    if !status.success() {
+        anyhow::bail!("Desktop notification command exited with {}", status);
+    }
+
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+fn notification_command(title: &str, body: &str) -> Result<Command> {
+    let script = format!(
+        "display notification {} with title {}",
+        applescript_string(body),
+        applescript_string(title)
+    );
+    let mut command = Command::new("osascript");
+    command.arg("-e").arg(script);
+    Ok(command)
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn notification_command(title: &str, body: &str) -> Result<Command> {
+    let mut command = Command::new("notify-send");
+    command.arg("--app-name=otot").arg(title).arg(body);
+    Ok(command)
+}
+
+#[cfg(not(unix))]
+fn notification_command(_title: &str, _body: &str) -> Result<Command> {
+    anyhow::bail!("Desktop notifications are not supported on this platform")
+}
+
+#[cfg(any(target_os = "macos", test))]
+fn applescript_string(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn applescript_string_escapes_quotes_and_backslashes() {
+        assert_eq!(
+            applescript_string(r#"say "hi" \o/"#),
+            r#""say \"hi\" \\o/""#
+        );
+    }
+}
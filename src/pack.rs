@@ -0,0 +1,326 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::process::{Command, Stdio};
+
+use crate::config::OtotConfig;
+use crate::url_classify::is_public_suffix;
+
+/// Shortcuts a team can share: viewer templates and how internal hosts are read, never
+/// any history.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Pack {
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub viewers: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub known_hosts: Vec<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub host_schemes: BTreeMap<String, String>,
+}
+
+/// A setting the pack and the current config disagree on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PackConflict {
+    pub key: String,
+    pub current: String,
+    pub incoming: String,
+}
+
+/// A setting installing a pack would add, or change from `current`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PackChange {
+    pub key: String,
+    pub current: Option<String>,
+    pub incoming: String,
+}
+
+impl PackChange {
+    /// Whether the change has hosts opened over plain http, where anyone on the network
+    /// can read and alter the pages.
+    pub fn is_http_downgrade(&self) -> bool {
+        self.key.starts_with("classify.host_schemes.") && self.incoming == "http"
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct PackInstall {
+    pub added: usize,
+    pub replaced: usize,
+    /// Conflicting settings left as they were
+    pub kept: Vec<String>,
+}
+
+impl Pack {
+    pub fn from_config(config: &OtotConfig) -> Self {
+        Self {
+            viewers: config.viewers.clone(),
+            known_hosts: config.classify.known_hosts.clone(),
+            host_schemes: config.classify.host_schemes.clone(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Every setting in the pack as a config key and value.
+    fn entries(&self) -> impl Iterator<Item = (String, &String)> {
+        self.viewers
+            .iter()
+            .map(|(name, template)| (format!("viewers.{}", name), template))
+            .chain(self.host_schemes.iter().map(|(host, scheme)| {
+                let key = format!("classify.host_schemes.{}", host.to_ascii_lowercase());
+                (key, scheme)
+            }))
+    }
+
+    /// Checks every value as `otot config set` would, returning them in a config of their
+    /// own. Host schemes for a whole public suffix, such as `com`, are refused: they would
+    /// apply to every site registered under it.
+    fn validated(&self) -> Result<OtotConfig> {
+        if let Some(host) = self.host_schemes.keys().find(|host| is_public_suffix(host)) {
+            anyhow::bail!(
+                "The pack sets a scheme for {}, a public suffix that covers every site registered under it",
+                host
+            );
+        }
+        let mut validated = OtotConfig::default();
+        for (key, value) in self.entries() {
+            validated
+                .set_value(&key, value)
+                .with_context(|| format!("The pack's {} is invalid", key))?;
+        }
+        Ok(validated)
+    }
+}
+
+/// Reads a pack from a file, or downloads it with `curl` when `source` is an https URL.
+/// Plain http is refused, including through redirects, since anyone on the way could
+/// rewrite the pack.
+pub fn load_pack(source: &str) -> Result<Pack> {
+    if source.starts_with("http://") {
+        anyhow::bail!(
+            "Refusing to download a pack over plain http, where it could be altered on the way; use an https URL"
+        );
+    }
+    let text = if source.starts_with("https://") {
+        let output = Command::new("curl")
+            .args(["--silent", "--show-error", "--fail", "--location"])
+            .args(["--proto", "=https", "--proto-redir", "=https", source])
+            .stdin(Stdio::null())
+            .output()
+            .context("Failed to run curl; it is needed to install packs from a URL")?;
+        if !output.status.success() {
+            anyhow::bail!(
+                "Failed to download {}: {}",
+                source,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        String::from_utf8(output.stdout).context("Pack is not valid UTF-8")?
+    } else {
+        std::fs::read_to_string(source).with_context(|| format!("Failed to read {}", source))?
+    };
+
+    toml::from_str(&text).with_context(|| format!("{} is not a valid pack", source))
+}
+
+/// Every setting installing `pack` would add to `config` or change in it, checked as
+/// `install_pack` checks them.
+pub fn pack_changes(config: &OtotConfig, pack: &Pack) -> Result<Vec<PackChange>> {
+    let validated = pack.validated()?;
+
+    let mut changes = Vec::new();
+    for (key, incoming) in pack.entries() {
+        let incoming = validated
+            .get_value(&key)?
+            .unwrap_or_else(|| incoming.clone());
+        let current = config.get_value(&key)?;
+        if current.as_ref() != Some(&incoming) {
+            changes.push(PackChange {
+                key,
+                current,
+                incoming,
+            });
+        }
+    }
+    for host in &pack.known_hosts {
+        if !config.classify.known_hosts.contains(host) {
+            changes.push(PackChange {
+                key: "classify.known_hosts".to_string(),
+                current: None,
+                incoming: host.clone(),
+            });
+        }
+    }
+
+    Ok(changes)
+}
+
+/// Adds the pack's settings to `config`. Where both define a setting differently, `resolve`
+/// decides whether the pack's value replaces the current one. Every value is checked as
+/// `otot config set` would, and nothing is changed if one is invalid.
+pub fn install_pack(
+    config: &mut OtotConfig,
+    pack: &Pack,
+    mut resolve: impl FnMut(&PackConflict) -> Result<bool>,
+) -> Result<PackInstall> {
+    let validated = pack.validated()?;
+
+    let mut install = PackInstall::default();
+    for (key, incoming) in pack.entries() {
+        let incoming = validated
+            .get_value(&key)?
+            .unwrap_or_else(|| incoming.clone());
+        match config.get_value(&key)? {
+            None => install.added += 1,
+            Some(current) if current == incoming => continue,
+            Some(current) => {
+                let conflict = PackConflict {
+                    key: key.clone(),
+                    current,
+                    incoming: incoming.clone(),
+                };
+                if !resolve(&conflict)? {
+                    install.kept.push(key);
+                    continue;
+                }
+                install.replaced += 1;
+            }
+        }
+        config.set_value(&key, &incoming)?;
+    }
+
+    for host in &pack.known_hosts {
+        if !config.classify.known_hosts.contains(host) {
+            config.classify.known_hosts.push(host.clone());
+            install.added += 1;
+        }
+    }
+
+    Ok(install)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn team_pack() -> Pack {
+        toml::from_str(
+            r#"
+            known_hosts = ["wiki", "jenkins"]
+
+            [viewers]
+            archive = "https://archive.ph/{url}"
+            lite = "https://lite.example/?u={url_encoded}"
+
+            [host_schemes]
+            "*.internal" = "http"
+            "#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn exported_packs_round_trip() {
+        let mut config = OtotConfig::default();
+        install_pack(&mut config, &team_pack(), |_| Ok(true)).unwrap();
+
+        let exported = toml::to_string(&Pack::from_config(&config)).unwrap();
+
+        assert_eq!(toml::from_str::<Pack>(&exported).unwrap(), team_pack());
+        assert!(Pack::from_config(&OtotConfig::default()).is_empty());
+    }
+
+    #[test]
+    fn conflicts_are_resolved_one_by_one() {
+        let mut config = OtotConfig::default();
+        config
+            .set_value("viewers.archive", "https://web.archive.org/web/{url}")
+            .unwrap();
+        config.classify.known_hosts.push("wiki".to_string());
+        let mut asked = Vec::new();
+
+        let install = install_pack(&mut config, &team_pack(), |conflict| {
+            asked.push(conflict.key.clone());
+            Ok(false)
+        })
+        .unwrap();
+
+        assert_eq!(asked, ["viewers.archive"]);
+        assert_eq!(
+            install,
+            PackInstall {
+                added: 3,
+                replaced: 0,
+                kept: vec!["viewers.archive".to_string()],
+            }
+        );
+        assert_eq!(
+            config.viewers["archive"],
+            "https://web.archive.org/web/{url}"
+        );
+        assert_eq!(config.classify.known_hosts, ["wiki", "jenkins"]);
+    }
+
+    #[test]
+    fn invalid_packs_change_nothing() {
+        let mut config = OtotConfig::default();
+        let mut pack = team_pack();
+        pack.host_schemes
+            .insert("ftp.internal".to_string(), "ftp".to_string());
+
+        let err = install_pack(&mut config, &pack, |_| Ok(true)).unwrap_err();
+
+        assert!(format!("{:#}", err).contains("classify.host_schemes.ftp.internal"));
+        assert!(config.viewers.is_empty());
+    }
+
+    #[test]
+    fn changes_list_what_the_pack_adds_and_flags_plain_http() {
+        let mut config = OtotConfig::default();
+        config.classify.known_hosts.push("wiki".to_string());
+        config
+            .set_value("viewers.lite", "https://lite.example/?u={url_encoded}")
+            .unwrap();
+
+        let changes = pack_changes(&config, &team_pack()).unwrap();
+
+        let keys: Vec<&str> = changes.iter().map(|c| c.key.as_str()).collect();
+        assert_eq!(
+            keys,
+            [
+                "viewers.archive",
+                "classify.host_schemes.*.internal",
+                "classify.known_hosts"
+            ]
+        );
+        let downgrades: Vec<&str> = changes
+            .iter()
+            .filter(|c| c.is_http_downgrade())
+            .map(|c| c.key.as_str())
+            .collect();
+        assert_eq!(downgrades, ["classify.host_schemes.*.internal"]);
+    }
+
+    #[test]
+    fn schemes_for_public_suffixes_are_refused() {
+        for host in ["com", "*.com", "co.uk"] {
+            let mut config = OtotConfig::default();
+            let mut pack = team_pack();
+            pack.host_schemes
+                .insert(host.to_string(), "http".to_string());
+
+            assert!(pack_changes(&config, &pack).is_err(), "{}", host);
+            let err = install_pack(&mut config, &pack, |_| Ok(true)).unwrap_err();
+            assert!(err.to_string().contains("public suffix"), "{}", host);
+            assert!(config.classify.host_schemes.is_empty());
+        }
+    }
+
+    #[test]
+    fn packs_are_not_downloaded_over_plain_http() {
+        let err = load_pack("http://wiki.example.com/team.toml").unwrap_err();
+        assert!(err.to_string().contains("plain http"));
+    }
+}
@@ -0,0 +1,109 @@
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+
+/// Dropping a file with this name next to the executable turns on portable mode.
+pub const PORTABLE_FLAG_FILE: &str = "portable.flag";
+const PORTABLE_DATA_DIR: &str = "otot-data";
+
+/// Where otot keeps its config file and its data (history database, audit log).
+#[derive(Debug, Clone, PartialEq)]
+pub struct AppPaths {
+    pub config_file: PathBuf,
+    pub data_dir: PathBuf,
+    pub portable: bool,
+}
+
+impl AppPaths {
+    /// Uses the per-user platform directories, unless portable mode is requested with
+    /// `portable` or a `portable.flag` file next to the executable.
+    pub fn resolve(portable: bool) -> Result<Self> {
+        let exe_dir = std::env::current_exe()
+            .ok()
+            .and_then(|exe| exe.canonicalize().ok())
+            .and_then(|exe| exe.parent().map(Path::to_path_buf));
+
+        match exe_dir {
+            Some(dir) if portable || dir.join(PORTABLE_FLAG_FILE).exists() => {
+                Ok(Self::portable_in(&dir))
+            }
+            None if portable => {
+                anyhow::bail!("Portable mode needs the executable's location, which is unknown")
+            }
+            _ => Self::user_default(),
+        }
+    }
+
+    pub fn portable_in(dir: &Path) -> Self {
+        let data_dir = dir.join(PORTABLE_DATA_DIR);
+        Self {
+            config_file: data_dir.join("config.toml"),
+            data_dir,
+            portable: true,
+        }
+    }
+
+    pub fn user_default() -> Result<Self> {
+        let data_dir = dirs::data_local_dir()
+            .context("Could not find local data directory")?
+            .join("otot");
+        let config_file = confy::get_configuration_file_path("otot", None)
+            .context("Failed to get config path")?;
+
+        Ok(Self {
+            config_file,
+            data_dir,
+            portable: false,
+        })
+    }
+
+    pub fn database_file(&self) -> PathBuf {
+        self.data_dir.join("history.db")
+    }
+
+    pub fn audit_log(&self) -> PathBuf {
+        self.data_dir.join("audit.jsonl")
+    }
+
+    /// URLs removed with "archive" in `prune --interactive`, one JSON object per line.
+    pub fn archive_file(&self) -> PathBuf {
+        self.data_dir.join("archive.jsonl")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn portable_paths_stay_next_to_the_executable() {
+        let paths = AppPaths::portable_in(Path::new("/media/usb/tools"));
+
+        assert!(paths.portable);
+        assert_eq!(
+            paths.database_file(),
+            Path::new("/media/usb/tools/otot-data/history.db")
+        );
+        assert_eq!(
+            paths.config_file,
+            Path::new("/media/usb/tools/otot-data/config.toml")
+        );
+        assert_eq!(
+            paths.audit_log(),
+            Path::new("/media/usb/tools/otot-data/audit.jsonl")
+        );
+    }
+
+    #[test]
+    fn explicit_portable_flag_uses_executable_directory() {
+        let paths = AppPaths::resolve(true).unwrap();
+        let exe_dir = std::env::current_exe()
+            .unwrap()
+            .canonicalize()
+            .unwrap()
+            .parent()
+            .unwrap()
+            .to_path_buf();
+
+        assert_eq!(paths, AppPaths::portable_in(&exe_dir));
+    }
+}
@@ -0,0 +1,182 @@
+use anyhow::{Context, Result};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+
+#[cfg(unix)]
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+
+#[cfg(unix)]
+const PRIVATE_DIR_MODE: u32 = 0o700;
+#[cfg(unix)]
+const PRIVATE_FILE_MODE: u32 = 0o600;
+
+/// Creates `path` (and any missing parents) and restricts it to the current user,
+/// independent of the process umask.
+pub fn create_private_dir(path: &Path) -> Result<()> {
+    std::fs::create_dir_all(path)
+        .with_context(|| format!("Failed to create directory {}", path.display()))?;
+
+    #[cfg(unix)]
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(PRIVATE_DIR_MODE))
+        .with_context(|| format!("Failed to set permissions on {}", path.display()))?;
+
+    Ok(())
+}
+
+/// Restricts an existing file to read/write for the current user only.
+pub fn restrict_file(path: &Path) -> Result<()> {
+    #[cfg(unix)]
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(PRIVATE_FILE_MODE))
+        .with_context(|| format!("Failed to set permissions on {}", path.display()))?;
+
+    #[cfg(not(unix))]
+    let _ = path;
+
+    Ok(())
+}
+
+/// Options that create a file readable and writable by the current user only, whatever
+/// the umask, so it is never readable by others even for a moment. A file that already
+/// exists keeps its mode.
+pub fn private_open_options() -> OpenOptions {
+    let mut options = OpenOptions::new();
+    #[cfg(unix)]
+    options.mode(PRIVATE_FILE_MODE);
+    options
+}
+
+/// Writes `contents` to `path`, created private, or restricted before anything is written
+/// if it already exists.
+pub fn write_private_file(path: &Path, contents: &[u8]) -> Result<()> {
+    let mut file = private_open_options()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(path)
+        .with_context(|| format!("Failed to create {}", path.display()))?;
+    restrict_file(path)?;
+    file.write_all(contents)
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Creates `path` empty and private unless it exists, so a program that would create it
+/// under the umask, such as SQLite, finds it already restricted.
+pub fn create_private_file(path: &Path) -> Result<()> {
+    match private_open_options()
+        .write(true)
+        .create_new(true)
+        .open(path)
+    {
+        Ok(_) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => Ok(()),
+        Err(e) => Err(e).with_context(|| format!("Failed to create {}", path.display())),
+    }
+}
+
+pub fn is_world_writable(path: &Path) -> Result<bool> {
+    #[cfg(unix)]
+    {
+        let metadata = std::fs::metadata(path)
+            .with_context(|| format!("Failed to read metadata for {}", path.display()))?;
+        Ok(metadata.permissions().mode() & 0o002 != 0)
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = path;
+        Ok(false)
+    }
+}
+
+pub fn ensure_not_world_writable(dir: &Path) -> Result<()> {
+    if dir.exists() && is_world_writable(dir)? {
+        anyhow::bail!(
+            "Refusing to use {} because it is world-writable. Restrict it with `chmod 700 {}` or pass --insecure",
+            dir.display(),
+            dir.display()
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use assert_fs::TempDir;
+
+    fn mode_of(path: &Path) -> u32 {
+        std::fs::metadata(path).unwrap().permissions().mode() & 0o777
+    }
+
+    #[test]
+    fn create_private_dir_tightens_existing_directory() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path().join("otot");
+        std::fs::create_dir(&dir).unwrap();
+        std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        create_private_dir(&dir).unwrap();
+
+        assert_eq!(mode_of(&dir), 0o700);
+    }
+
+    #[test]
+    fn restrict_file_sets_owner_only_mode() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("history.db");
+        std::fs::write(&file, b"").unwrap();
+        std::fs::set_permissions(&file, std::fs::Permissions::from_mode(0o644)).unwrap();
+
+        restrict_file(&file).unwrap();
+
+        assert_eq!(mode_of(&file), 0o600);
+    }
+
+    #[test]
+    fn private_files_are_created_owner_only_and_existing_ones_tightened() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("export.json");
+
+        write_private_file(&file, b"{}").unwrap();
+        assert_eq!(mode_of(&file), 0o600);
+
+        std::fs::set_permissions(&file, std::fs::Permissions::from_mode(0o644)).unwrap();
+        write_private_file(&file, b"[]").unwrap();
+        assert_eq!(mode_of(&file), 0o600);
+        assert_eq!(std::fs::read(&file).unwrap(), b"[]");
+    }
+
+    #[test]
+    fn create_private_file_leaves_existing_files_alone() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("history.db");
+
+        create_private_file(&file).unwrap();
+        assert_eq!(mode_of(&file), 0o600);
+
+        std::fs::write(&file, b"data").unwrap();
+        create_private_file(&file).unwrap();
+        assert_eq!(std::fs::read(&file).unwrap(), b"data");
+    }
+
+    #[test]
+    fn world_writable_directory_is_rejected() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::set_permissions(temp_dir.path(), std::fs::Permissions::from_mode(0o777)).unwrap();
+
+        let result = ensure_not_world_writable(temp_dir.path());
+
+        assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains("--insecure"));
+    }
+
+    #[test]
+    fn private_directory_is_accepted() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::set_permissions(temp_dir.path(), std::fs::Permissions::from_mode(0o700)).unwrap();
+
+        assert!(ensure_not_world_writable(temp_dir.path()).is_ok());
+    }
+}
@@ -0,0 +1,244 @@
+use anyhow::{Context, Result};
+use log::debug;
+use std::process::{Command, Stdio};
+use std::thread;
+
+use crate::database::{Database, PagePreview};
+
+/// How many pages are fetched at once, how long each may take, and how long a preview is
+/// trusted before it is fetched again.
+const PREVIEW_CONCURRENCY: usize = 4;
+const PREVIEW_TIMEOUT_SECS: &str = "3";
+const PREVIEW_MAX_AGE_SECS: i64 = 86400;
+/// Titles sit in the page head, so the rest of a large page is never downloaded.
+const PREVIEW_MAX_BYTES: usize = 64 * 1024;
+
+pub trait PageFetcher: Sync {
+    /// Returns the HTTP status, or `None` when the server could not be reached, and the
+    /// start of the page body.
+    fn fetch(&self, url: &str) -> Result<(Option<u16>, String)>;
+}
+
+/// Fetches pages with `curl`, without cookies or credentials.
+pub struct CurlFetcher;
+impl PageFetcher for CurlFetcher {
+    fn fetch(&self, url: &str) -> Result<(Option<u16>, String)> {
+        let output = Command::new("curl")
+            .args(["--silent", "--location", "--max-time", PREVIEW_TIMEOUT_SECS])
+            .args(["--range", &format!("0-{}", PREVIEW_MAX_BYTES - 1)])
+            .args(["--write-out", "\n%{http_code}"])
+            .arg(url)
+            .stdin(Stdio::null())
+            .output()
+            .context("Failed to run curl; it is needed for previews")?;
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let (body, code) = stdout.rsplit_once('\n').unwrap_or(("", &stdout));
+        let status = code.trim().parse().ok().filter(|&code| code != 0);
+        Ok((status, body.to_string()))
+    }
+}
+
+/// Previews for `urls` in order, from the cache where fresh and fetched otherwise, a few
+/// at a time. A page that fails to fetch gets no preview rather than an error.
+pub fn preview_pages(
+    db: &mut dyn Database,
+    fetcher: &dyn PageFetcher,
+    urls: &[String],
+    now: i64,
+) -> Result<Vec<Option<PagePreview>>> {
+    let mut previews = urls
+        .iter()
+        .map(|url| db.page_preview(url, now - PREVIEW_MAX_AGE_SECS))
+        .collect::<Result<Vec<_>>>()?;
+
+    let missing: Vec<usize> = (0..urls.len()).filter(|&i| previews[i].is_none()).collect();
+    for batch in missing.chunks(PREVIEW_CONCURRENCY) {
+        let fetched: Vec<_> = thread::scope(|scope| {
+            let handles: Vec<_> = batch
+                .iter()
+                .map(|&i| scope.spawn(move || fetcher.fetch(&urls[i])))
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| {
+                    handle
+                        .join()
+                        .unwrap_or_else(|_| Err(anyhow::anyhow!("panicked")))
+                })
+                .collect()
+        });
+
+        for (&i, result) in batch.iter().zip(fetched) {
+            match result {
+                Ok((status, body)) => {
+                    let preview = PagePreview {
+                        status,
+                        title: extract_title(&body),
+                        fetched_at: now,
+                    };
+                    db.store_page_preview(&urls[i], &preview)?;
+                    previews[i] = Some(preview);
+                }
+                Err(e) => debug!("No preview for {}: {:#}", urls[i], e),
+            }
+        }
+    }
+
+    Ok(previews)
+}
+
+/// Stores the title of `url` unless one is stored already, using the cached preview when
+/// there is one. A page without a title is remembered as such; one that can't be reached
+/// is tried again on the next visit.
+pub fn store_page_title(
+    db: &mut dyn Database,
+    fetcher: &dyn PageFetcher,
+    url: &str,
+    now: i64,
+) -> Result<()> {
+    if !(url.starts_with("https://") || url.starts_with("http://")) || db.title(url)?.is_some() {
+        return Ok(());
+    }
+
+    let preview = preview_pages(db, fetcher, &[url.to_string()], now)?
+        .into_iter()
+        .flatten()
+        .next();
+    if let Some(preview) = preview
+        && preview.status.is_some()
+    {
+        db.set_title(url, preview.title.as_deref().unwrap_or_default())?;
+    }
+    Ok(())
+}
+
+/// A short description for the picker, e.g. `Rust [200]` or `[unreachable]`.
+pub fn describe_preview(preview: &PagePreview) -> String {
+    let status = match preview.status {
+        Some(status) => status.to_string(),
+        None => "unreachable".to_string(),
+    };
+    match &preview.title {
+        Some(title) => format!("{} [{}]", title, status),
+        None => format!("[{}]", status),
+    }
+}
+
+/// The text of the first `<title>` element, with whitespace collapsed and the common
+/// entities decoded.
+pub fn extract_title(html: &str) -> Option<String> {
+    let lower = html.to_ascii_lowercase();
+    let open = lower.find("<title")?;
+    let start = open + lower[open..].find('>')? + 1;
+    let end = start + lower[start..].find("</title")?;
+
+    let title = html[start..end]
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&");
+    Some(title).filter(|title| !title.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::SqliteDatabase;
+    use assert_fs::TempDir;
+    use std::sync::Mutex;
+
+    struct FakeFetcher {
+        fetched: Mutex<Vec<String>>,
+    }
+
+    impl PageFetcher for FakeFetcher {
+        fn fetch(&self, url: &str) -> Result<(Option<u16>, String)> {
+            self.fetched.lock().unwrap().push(url.to_string());
+            match url {
+                "https://example.com/gone" => Ok((Some(404), "<title>Not Found</title>".into())),
+                "https://example.com/down" => Ok((None, String::new())),
+                "https://example.com/book" => {
+                    Ok((Some(200), "<title>References and Borrowing</title>".into()))
+                }
+                "https://example.com/plain" => Ok((Some(200), "no markup".into())),
+                _ => anyhow::bail!("curl missing"),
+            }
+        }
+    }
+
+    #[test]
+    fn titles_are_extracted_and_cleaned() {
+        assert_eq!(
+            extract_title("<html><head><TITLE lang=en>\n  Rust &amp; Cargo\n</TITLE>").as_deref(),
+            Some("Rust & Cargo")
+        );
+        assert_eq!(extract_title("<title></title>"), None);
+        assert_eq!(extract_title("no markup"), None);
+    }
+
+    #[test]
+    fn previews_are_fetched_once_and_cached() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = SqliteDatabase::open_at(&temp_dir.path().join("test.db")).unwrap();
+        let fetcher = FakeFetcher {
+            fetched: Mutex::new(Vec::new()),
+        };
+        let urls = vec![
+            "https://example.com/gone".to_string(),
+            "https://example.com/down".to_string(),
+            "https://example.com/error".to_string(),
+        ];
+
+        let previews = preview_pages(&mut db, &fetcher, &urls, 1000).unwrap();
+        preview_pages(&mut db, &fetcher, &urls, 2000).unwrap();
+
+        assert_eq!(
+            previews
+                .iter()
+                .map(|p| p.as_ref().map(describe_preview))
+                .collect::<Vec<_>>(),
+            vec![
+                Some("Not Found [404]".to_string()),
+                Some("[unreachable]".to_string()),
+                None
+            ]
+        );
+        // Only the failed fetch is retried
+        assert_eq!(fetcher.fetched.lock().unwrap().len(), 4);
+    }
+
+    #[test]
+    fn titles_are_stored_once_per_page() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = SqliteDatabase::open_at(&temp_dir.path().join("test.db")).unwrap();
+        let fetcher = FakeFetcher {
+            fetched: Mutex::new(Vec::new()),
+        };
+        let urls = [
+            "https://example.com/book",
+            "https://example.com/plain",
+            "https://example.com/down",
+        ];
+        for url in urls {
+            db.add_visit(url, std::time::SystemTime::now()).unwrap();
+            store_page_title(&mut db, &fetcher, url, 1000).unwrap();
+        }
+        store_page_title(&mut db, &fetcher, "https://example.com/book", 2000).unwrap();
+
+        let titles: Vec<Option<String>> = urls.iter().map(|url| db.title(url).unwrap()).collect();
+        assert_eq!(
+            titles,
+            [
+                Some("References and Borrowing".to_string()),
+                Some(String::new()),
+                None
+            ]
+        );
+        assert_eq!(fetcher.fetched.lock().unwrap().len(), 3);
+    }
+}
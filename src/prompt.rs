@@ -0,0 +1,20 @@
+use anyhow::Result;
+use std::io::{IsTerminal, Write};
+
+/// Whether there is someone to answer prompts: scripts, git hooks and cron jobs get
+/// predictable behavior instead of a question nobody sees.
+pub fn interactive() -> bool {
+    std::io::stdin().is_terminal() && std::io::stderr().is_terminal()
+}
+
+/// Asks `question` on the terminal; only an explicit yes counts.
+pub fn ask_yes_no(question: &str) -> Result<bool> {
+    eprint!("{}", question);
+    std::io::stderr().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    Ok(matches!(
+        answer.trim().to_ascii_lowercase().as_str(),
+        "y" | "yes"
+    ))
+}
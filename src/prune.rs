@@ -0,0 +1,253 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::io::{BufRead, Write};
+use std::path::Path;
+
+use crate::database::{Database, HistoryEntry};
+use crate::locale::Locale;
+use crate::permissions::{create_private_dir, private_open_options, restrict_file};
+use crate::{format_relative_time, unix_now};
+
+const SAMPLE_URLS: usize = 3;
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PruneWizardSummary {
+    pub domains_reviewed: usize,
+    pub kept: usize,
+    pub archived: usize,
+    pub deleted: usize,
+}
+
+#[derive(Serialize)]
+struct ArchivedUrl<'a> {
+    #[serde(flatten)]
+    entry: &'a HistoryEntry,
+    archived_at: i64,
+}
+
+enum Choice {
+    Keep,
+    Archive,
+    Delete,
+    Quit,
+}
+
+/// Walks through every domain in the history, stalest first, asking whether to keep,
+/// archive or delete its URLs. Archived URLs are appended to `archive_path` before they are
+/// removed. Stops early when the user quits or `input` runs out.
+pub fn run_prune_wizard(
+    db: &mut dyn Database,
+    input: &mut dyn BufRead,
+    output: &mut dyn Write,
+    archive_path: &Path,
+    locale: &Locale,
+) -> Result<PruneWizardSummary> {
+    let domains = db.domain_summaries(SAMPLE_URLS)?;
+    let mut summary = PruneWizardSummary::default();
+
+    if domains.is_empty() {
+        writeln!(output, "No URLs in history yet.")?;
+        return Ok(summary);
+    }
+
+    for (index, domain) in domains.iter().enumerate() {
+        writeln!(
+            output,
+            "\n[{}/{}] {}: {} URL(s), last visited {}",
+            index + 1,
+            domains.len(),
+            domain.domain,
+            locale.count(domain.urls),
+            format_relative_time(domain.last_accessed)
+        )?;
+        for sample in &domain.samples {
+            writeln!(output, "    {}", sample)?;
+        }
+        if domain.urls > domain.samples.len() as i64 {
+            writeln!(
+                output,
+                "    ... and {} more",
+                locale.count(domain.urls - domain.samples.len() as i64)
+            )?;
+        }
+
+        let urls = domain.urls as usize;
+        match prompt(input, output)? {
+            Choice::Keep => summary.kept += urls,
+            Choice::Archive => {
+                let entries = db.domain_entries(&domain.domain)?;
+                append_archive(archive_path, &entries)?;
+                summary.archived += db.prune_by_domain(&domain.domain)?;
+            }
+            Choice::Delete => summary.deleted += db.prune_by_domain(&domain.domain)?,
+            Choice::Quit => break,
+        }
+        summary.domains_reviewed += 1;
+
+        writeln!(
+            output,
+            "Running total: {} kept, {} archived, {} deleted",
+            locale.count(summary.kept as u64),
+            locale.count(summary.archived as u64),
+            locale.count(summary.deleted as u64)
+        )?;
+    }
+
+    Ok(summary)
+}
+
+fn prompt(input: &mut dyn BufRead, output: &mut dyn Write) -> Result<Choice> {
+    loop {
+        write!(output, "Keep, archive, delete or quit? [k/a/d/q] ")?;
+        output.flush()?;
+
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            writeln!(output)?;
+            return Ok(Choice::Quit);
+        }
+
+        match line.trim().to_ascii_lowercase().as_str() {
+            "k" | "keep" => return Ok(Choice::Keep),
+            "a" | "archive" => return Ok(Choice::Archive),
+            "d" | "delete" => return Ok(Choice::Delete),
+            "q" | "quit" => return Ok(Choice::Quit),
+            other => writeln!(output, "Unknown choice '{}'", other)?,
+        }
+    }
+}
+
+fn append_archive(path: &Path, entries: &[HistoryEntry]) -> Result<()> {
+    if let Some(parent) = path.parent()
+        && !parent.exists()
+    {
+        create_private_dir(parent)?;
+    }
+
+    let mut file = private_open_options()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open archive {}", path.display()))?;
+    restrict_file(path)?;
+
+    let archived_at = unix_now();
+    let mut lines = String::new();
+    for entry in entries {
+        lines.push_str(&serde_json::to_string(&ArchivedUrl { entry, archived_at })?);
+        lines.push('\n');
+    }
+
+    // Written and synced before the URLs are deleted, so a failure loses nothing
+    file.write_all(lines.as_bytes())
+        .and_then(|()| file.sync_data())
+        .with_context(|| format!("Failed to write archive {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{HistoryFilter, SqliteDatabase};
+    use assert_fs::TempDir;
+    use std::time::{Duration, SystemTime};
+
+    fn history_db(temp_dir: &TempDir) -> SqliteDatabase {
+        let mut db = SqliteDatabase::open_at(&temp_dir.path().join("history.db")).unwrap();
+        let at = |secs| SystemTime::UNIX_EPOCH + Duration::from_secs(secs);
+        db.add_visit("https://stale.com/a", at(1000)).unwrap();
+        db.add_visit("https://stale.com/b", at(1000)).unwrap();
+        db.add_visit("https://middle.com/a", at(2000)).unwrap();
+        db.add_visit("https://fresh.com/a", at(3000)).unwrap();
+        db
+    }
+
+    fn remaining_urls(db: &SqliteDatabase) -> Vec<String> {
+        let mut urls: Vec<String> = db
+            .history(&HistoryFilter::default())
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.url)
+            .collect();
+        urls.sort();
+        urls
+    }
+
+    #[test]
+    fn choices_apply_per_domain_with_running_totals() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = history_db(&temp_dir);
+        let archive = temp_dir.path().join("archive.jsonl");
+        let mut output = Vec::new();
+
+        let summary = run_prune_wizard(
+            &mut db,
+            &mut "a\nd\nk\n".as_bytes(),
+            &mut output,
+            &archive,
+            &Locale::default(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            summary,
+            PruneWizardSummary {
+                domains_reviewed: 3,
+                kept: 1,
+                archived: 2,
+                deleted: 1,
+            }
+        );
+        assert_eq!(remaining_urls(&db), vec!["https://fresh.com/a"]);
+
+        let archived = std::fs::read_to_string(&archive).unwrap();
+        assert_eq!(archived.lines().count(), 2);
+        assert!(archived.contains("\"url\":\"https://stale.com/a\""));
+        assert!(archived.contains("\"archived_at\":"));
+
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("[1/3] stale.com: 2 URL(s)"));
+        assert!(output.contains("Running total: 1 kept, 2 archived, 1 deleted"));
+    }
+
+    #[test]
+    fn invalid_choice_asks_again() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = history_db(&temp_dir);
+        let mut output = Vec::new();
+
+        let summary = run_prune_wizard(
+            &mut db,
+            &mut "nope\nd\nq\n".as_bytes(),
+            &mut output,
+            &temp_dir.path().join("archive.jsonl"),
+            &Locale::default(),
+        )
+        .unwrap();
+
+        assert_eq!(summary.deleted, 2);
+        assert_eq!(summary.domains_reviewed, 1);
+        assert!(
+            String::from_utf8(output)
+                .unwrap()
+                .contains("Unknown choice 'nope'")
+        );
+    }
+
+    #[test]
+    fn end_of_input_stops_without_changes() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = history_db(&temp_dir);
+
+        let summary = run_prune_wizard(
+            &mut db,
+            &mut "".as_bytes(),
+            &mut Vec::new(),
+            &temp_dir.path().join("archive.jsonl"),
+            &Locale::default(),
+        )
+        .unwrap();
+
+        assert_eq!(summary, PruneWizardSummary::default());
+        assert_eq!(remaining_urls(&db).len(), 4);
+    }
+}
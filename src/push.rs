@@ -0,0 +1,114 @@
+use anyhow::{Context, Result};
+use log::debug;
+use std::process::{Command, Stdio};
+
+const DEFAULT_RELAY_HOST: &str = "https://ntfy.sh";
+
+/// Sends `url` to the phone subscribed to `relay`, an ntfy-compatible topic URL such as
+/// `https://ntfy.sh/<topic>`. Tapping the notification opens the URL.
+pub fn push_url(relay: &str, url: &str) -> Result<()> {
+    validate_relay(relay)?;
+
+    let mut command = push_command(relay, url);
+    debug!("Pushing URL: {:?}", command);
+
+    let output = command
+        .stdin(Stdio::null())
+        .output()
+        .context("Failed to run curl; it is needed to push URLs")?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "Pushing to {} failed: {}",
+            relay,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    Ok(())
+}
+
+fn push_command(relay: &str, url: &str) -> Command {
+    let mut command = Command::new("curl");
+    command
+        .args(["--fail", "--silent", "--show-error", "--max-time", "10"])
+        .args(["-H", "Title: otot"])
+        .arg("-H")
+        .arg(format!("Click: {}", url))
+        .arg("--data-binary")
+        .arg(url)
+        .arg(relay);
+    command
+}
+
+pub fn validate_relay(relay: &str) -> Result<()> {
+    let parsed =
+        url::Url::parse(relay).with_context(|| format!("Invalid push relay '{}'", relay))?;
+    if !matches!(parsed.scheme(), "http" | "https") || parsed.path().trim_matches('/').is_empty() {
+        anyhow::bail!(
+            "Push relay must be an http(s) topic URL like {}/<topic>, got '{}'",
+            DEFAULT_RELAY_HOST,
+            relay
+        );
+    }
+    Ok(())
+}
+
+/// A fresh topic on the public ntfy.sh relay. Anyone who knows the topic can read the
+/// pushes, so it is long and random.
+pub fn new_relay_topic() -> Result<String> {
+    let mut random = [0u8; 16];
+    getrandom::fill(&mut random)
+        .map_err(|e| anyhow::anyhow!("Failed to read random bytes from the system: {}", e))?;
+    let hex: String = random.iter().map(|byte| format!("{:02x}", byte)).collect();
+    Ok(format!("{}/otot-{}", DEFAULT_RELAY_HOST, hex))
+}
+
+/// Prints `text` as a terminal QR code with `qrencode`. Returns `false` when `qrencode` is
+/// not installed.
+pub fn print_qr_code(text: &str) -> Result<bool> {
+    match Command::new("qrencode")
+        .args(["-t", "ANSIUTF8"])
+        .arg(text)
+        .status()
+    {
+        Ok(status) if status.success() => Ok(true),
+        Ok(status) => anyhow::bail!("qrencode exited with {}", status),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+        Err(e) => Err(e).context("Failed to run qrencode"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_topics_are_valid_and_unique() {
+        let first = new_relay_topic().unwrap();
+        let second = new_relay_topic().unwrap();
+
+        assert!(first.starts_with("https://ntfy.sh/otot-"));
+        assert_eq!(first.len(), "https://ntfy.sh/otot-".len() + 32);
+        assert_ne!(first, second);
+        assert!(validate_relay(&first).is_ok());
+    }
+
+    #[test]
+    fn relay_must_be_an_http_topic() {
+        assert!(validate_relay("https://ntfy.example.com/my-topic").is_ok());
+        assert!(validate_relay("https://ntfy.sh/").is_err());
+        assert!(validate_relay("ftp://ntfy.sh/topic").is_err());
+        assert!(validate_relay("ntfy.sh/topic").is_err());
+    }
+
+    #[test]
+    fn push_command_makes_notification_clickable() {
+        let command = push_command("https://ntfy.sh/t", "https://example.com/a");
+        let args: Vec<_> = command.get_args().map(|a| a.to_string_lossy()).collect();
+
+        assert_eq!(command.get_program(), "curl");
+        assert!(args.contains(&"Click: https://example.com/a".into()));
+        assert_eq!(args.last().unwrap(), "https://ntfy.sh/t");
+    }
+}
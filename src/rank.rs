@@ -0,0 +1,209 @@
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::database::calculate_frecency;
+
+/// What a ranker knows about one URL that matched a pattern.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Candidate {
+    /// Visits, decayed over time
+    pub score: f64,
+    pub last_accessed: i64,
+    /// How well the URL's segments matched the pattern, e.g. 100 per exact segment
+    pub match_quality: i64,
+    pub url_segments: usize,
+    pub pattern_segments: usize,
+}
+
+/// Orders the URLs matching a pattern; higher values rank first. Values are compared as
+/// ratios to find near ties, so they should be positive.
+pub trait Ranker {
+    fn rank(&self, candidate: &Candidate, now: i64) -> f64;
+}
+
+/// Frecency boosted by how well the URL matched and how close its length is to the
+/// pattern's, so good matches rise without burying the URLs visited most.
+pub struct CombinedRanker;
+impl Ranker for CombinedRanker {
+    fn rank(&self, candidate: &Candidate, _now: i64) -> f64 {
+        calculate_frecency(candidate.score, candidate.last_accessed)
+            * match_quality_multiplier(candidate.match_quality)
+            * segment_proximity_multiplier(candidate.pattern_segments, candidate.url_segments)
+    }
+}
+
+/// Frecency alone, ignoring how well the URL matched.
+pub struct FrecencyRanker;
+impl Ranker for FrecencyRanker {
+    fn rank(&self, candidate: &Candidate, _now: i64) -> f64 {
+        calculate_frecency(candidate.score, candidate.last_accessed)
+    }
+}
+
+/// Most recently visited first.
+pub struct RecencyRanker;
+impl Ranker for RecencyRanker {
+    fn rank(&self, candidate: &Candidate, now: i64) -> f64 {
+        let hours_ago = (now - candidate.last_accessed).max(0) as f64 / 3600.0;
+        1.0 / (1.0 + hours_ago)
+    }
+}
+
+/// Most visited first, however long ago.
+pub struct ScoreRanker;
+impl Ranker for ScoreRanker {
+    fn rank(&self, candidate: &Candidate, _now: i64) -> f64 {
+        candidate.score
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum RankStrategy {
+    /// Frecency weighted by match quality
+    #[default]
+    Combined,
+    /// Visits weighted by how recent they are
+    Frecency,
+    /// Most recently visited first
+    Recency,
+    /// Most visited first
+    Score,
+}
+
+impl RankStrategy {
+    pub fn ranker(self) -> &'static dyn Ranker {
+        match self {
+            Self::Combined => &CombinedRanker,
+            Self::Frecency => &FrecencyRanker,
+            Self::Recency => &RecencyRanker,
+            Self::Score => &ScoreRanker,
+        }
+    }
+}
+
+/// Converts a raw match score into a multiplier for ranking.
+/// Returns a value in the range [0.5, 1.5] to boost good matches
+/// without completely burying high-frecency URLs with weaker matches.
+fn match_quality_multiplier(match_score: i64) -> f64 {
+    // Score ranges:
+    // - Single segment exact match: 100
+    // - Two segment exact matches: 200
+    // - Fuzzy matches: typically 10-50 per segment
+    //
+    // We normalize to [0.5, 1.5]:
+    // - score <= 20: 0.5 (weak fuzzy match)
+    // - score >= 200: 1.5 (perfect match)
+    // - Linear interpolation between
+    let normalized = ((match_score as f64 - 20.0) / 180.0).clamp(0.0, 1.0);
+    0.5 + normalized
+}
+
+/// Converts segment count difference into a multiplier for ranking.
+/// URLs with segment counts closer to the pattern length are boosted.
+/// Returns a value in the range [0.7, 1.3]:
+/// - Exact match (diff = 0): 1.3
+/// - Small difference (diff = 1-2): ~1.1-1.2
+/// - Large difference (diff >= 5): 0.7
+fn segment_proximity_multiplier(pattern_len: usize, url_segment_len: usize) -> f64 {
+    let diff = url_segment_len.abs_diff(pattern_len);
+
+    // Normalize: diff 0 -> 1.0, diff 5+ -> 0.0
+    let normalized = 1.0 - (diff as f64 / 5.0).min(1.0);
+
+    // Map to [0.7, 1.3] range
+    0.7 + (normalized * 0.6)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: i64 = 1_700_000_000;
+
+    fn candidate(score: f64, hours_ago: i64, match_quality: i64) -> Candidate {
+        Candidate {
+            score,
+            last_accessed: NOW - hours_ago * 3600,
+            match_quality,
+            url_segments: 2,
+            pattern_segments: 2,
+        }
+    }
+
+    #[test]
+    fn strategies_disagree_on_an_old_favourite_and_a_new_page() {
+        let favourite = candidate(50.0, 24 * 30, 200);
+        let fresh = candidate(1.0, 0, 40);
+        let prefers_fresh = |strategy: RankStrategy| {
+            let ranker = strategy.ranker();
+            ranker.rank(&fresh, NOW) > ranker.rank(&favourite, NOW)
+        };
+
+        assert!(prefers_fresh(RankStrategy::Recency));
+        assert!(!prefers_fresh(RankStrategy::Score));
+    }
+
+    #[test]
+    fn combined_rewards_better_matches_where_frecency_does_not() {
+        let exact = candidate(2.0, 2, 200);
+        let fuzzy = candidate(2.0, 2, 20);
+
+        assert!(CombinedRanker.rank(&exact, NOW) > CombinedRanker.rank(&fuzzy, NOW));
+        assert_eq!(
+            FrecencyRanker.rank(&exact, NOW),
+            FrecencyRanker.rank(&fuzzy, NOW)
+        );
+    }
+
+    // ===========================================
+    // match_quality_multiplier tests
+    // ===========================================
+
+    #[test]
+    fn multiplier_weak_match_returns_low() {
+        let mult = match_quality_multiplier(10);
+        assert!(mult < 0.6);
+    }
+
+    #[test]
+    fn multiplier_perfect_match_returns_high() {
+        let mult = match_quality_multiplier(200);
+        assert!((mult - 1.5).abs() < 0.01);
+    }
+
+    #[test]
+    fn multiplier_medium_match_returns_middle() {
+        let mult = match_quality_multiplier(100);
+        assert!(mult > 0.8 && mult < 1.2);
+    }
+
+    // ===========================================
+    // segment_proximity_multiplier tests
+    // ===========================================
+
+    #[test]
+    fn segment_proximity_exact_match_returns_high() {
+        let mult = segment_proximity_multiplier(2, 2);
+        assert!((mult - 1.3).abs() < 0.01);
+    }
+
+    #[test]
+    fn segment_proximity_small_diff_returns_moderate() {
+        let mult = segment_proximity_multiplier(2, 3);
+        assert!(mult > 1.0 && mult < 1.3); // diff of 1
+    }
+
+    #[test]
+    fn segment_proximity_large_diff_returns_low() {
+        let mult = segment_proximity_multiplier(2, 7);
+        assert!((mult - 0.7).abs() < 0.01); // diff of 5+
+    }
+
+    #[test]
+    fn segment_proximity_pattern_longer_than_url() {
+        // This shouldn't happen normally, but the multiplier should handle it
+        let mult = segment_proximity_multiplier(5, 2);
+        assert!(mult > 0.9 && mult < 1.1); // diff of 3
+    }
+}
@@ -0,0 +1,238 @@
+use anyhow::{Context, Result};
+use log::debug;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+use url::Url;
+
+use crate::database::{Database, RedirectCheck};
+use crate::sanitize::redact_secrets;
+
+/// How many redirects are followed from one URL, how long each request may take, and how
+/// long a check is trusted before the URL is checked again.
+const MAX_REDIRECTS: usize = 5;
+const REDIRECT_TIMEOUT_SECS: &str = "3";
+const REDIRECT_MAX_AGE_SECS: i64 = 30 * 86400;
+
+pub trait RedirectProbe {
+    /// Returns the HTTP status of `url`, or `None` when the server could not be reached,
+    /// and the `Location` it redirects to, without following it.
+    fn probe(&self, url: &str) -> Result<(Option<u16>, Option<String>)>;
+}
+
+/// Asks with `curl` for the headers only, without cookies or credentials.
+pub struct CurlProbe;
+impl RedirectProbe for CurlProbe {
+    fn probe(&self, url: &str) -> Result<(Option<u16>, Option<String>)> {
+        let output = Command::new("curl")
+            .args(["--silent", "--head", "--max-time", REDIRECT_TIMEOUT_SECS])
+            .args(["--output", "/dev/null"])
+            .args(["--write-out", "%{http_code} %{redirect_url}"])
+            .arg(url)
+            .stdin(Stdio::null())
+            .output()
+            .context("Failed to run curl; it is needed to check redirects")?;
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let (code, location) = stdout.trim().split_once(' ').unwrap_or((&stdout, ""));
+        let status = code.trim().parse().ok().filter(|&code| code != 0);
+        let location = Some(location.trim().to_string()).filter(|l| !l.is_empty());
+        Ok((status, location))
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct RedirectSummary {
+    /// URLs asked about over the network
+    pub checked: usize,
+    /// URLs whose recent check was reused
+    pub cached: usize,
+    /// Each URL that moved permanently, with where it lives now
+    pub moved: Vec<(String, String)>,
+    /// URLs that could not be checked
+    pub failed: usize,
+}
+
+/// Checks the `count` URLs ranked highest for permanent redirects (301 and 308) and moves
+/// each one that has moved, with its score, to its new address; with `dry_run` nothing is
+/// moved. Requests are made one at a time, at least `pause` apart.
+pub fn canonicalize_redirects(
+    db: &mut dyn Database,
+    probe: &dyn RedirectProbe,
+    count: u16,
+    now: i64,
+    pause: Duration,
+    dry_run: bool,
+) -> Result<RedirectSummary> {
+    let mut summary = RedirectSummary::default();
+    let mut last_request: Option<Instant> = None;
+
+    for entry in db.top_candidates(count)? {
+        let url = entry.url;
+        if !is_web_url(&url) {
+            continue;
+        }
+
+        let location = match db.redirect_check(&url, now - REDIRECT_MAX_AGE_SECS)? {
+            Some(check) => {
+                summary.cached += 1;
+                check.location
+            }
+            None => {
+                let followed = follow_permanent_redirects(probe, &url, pause, &mut last_request);
+                match followed {
+                    Ok(location) => {
+                        summary.checked += 1;
+                        let check = RedirectCheck {
+                            location: location.clone(),
+                            checked_at: now,
+                        };
+                        db.store_redirect_check(&url, &check)?;
+                        location
+                    }
+                    Err(e) => {
+                        debug!("Could not check {}: {:#}", url, e);
+                        summary.failed += 1;
+                        continue;
+                    }
+                }
+            }
+        };
+
+        let Some(location) = location else {
+            continue;
+        };
+        if dry_run || db.move_url(&url, &location)? {
+            summary.moved.push((url, location));
+        }
+    }
+
+    Ok(summary)
+}
+
+/// Where `url` lives after following only permanent redirects, or `None` when it hasn't
+/// moved. A chain longer than `MAX_REDIRECTS`, or one leaving http(s), counts as not moved.
+fn follow_permanent_redirects(
+    probe: &dyn RedirectProbe,
+    url: &str,
+    pause: Duration,
+    last_request: &mut Option<Instant>,
+) -> Result<Option<String>> {
+    let mut current = Url::parse(url)?;
+    for _ in 0..=MAX_REDIRECTS {
+        if let Some(last) = last_request {
+            thread::sleep(pause.saturating_sub(last.elapsed()));
+        }
+        *last_request = Some(Instant::now());
+
+        let (status, location) = probe.probe(current.as_str())?;
+        let (Some(301 | 308), Some(location)) = (status, location) else {
+            let location = redact_secrets(current.as_str());
+            return Ok(Some(location).filter(|location| location != url));
+        };
+        current = current
+            .join(&location)
+            .with_context(|| format!("Invalid redirect to {}", location))?;
+        if !is_web_url(current.as_str()) {
+            return Ok(None);
+        }
+    }
+
+    Ok(None)
+}
+
+fn is_web_url(url: &str) -> bool {
+    url.starts_with("https://") || url.starts_with("http://")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::SqliteDatabase;
+    use assert_fs::TempDir;
+    use std::sync::Mutex;
+    use std::time::SystemTime;
+
+    struct FakeProbe {
+        probed: Mutex<Vec<String>>,
+    }
+
+    impl RedirectProbe for FakeProbe {
+        fn probe(&self, url: &str) -> Result<(Option<u16>, Option<String>)> {
+            self.probed.lock().unwrap().push(url.to_string());
+            Ok(match url {
+                "https://old.example.com/docs" => (Some(301), Some("/v2/docs".into())),
+                "https://old.example.com/v2/docs" => {
+                    (Some(308), Some("https://docs.example.com/".into()))
+                }
+                "https://example.com/login" => (Some(302), Some("/sso".into())),
+                "https://example.com/loop" => (Some(301), Some("/loop".into())),
+                _ => (Some(200), None),
+            })
+        }
+    }
+
+    fn probe() -> FakeProbe {
+        FakeProbe {
+            probed: Mutex::new(Vec::new()),
+        }
+    }
+
+    #[test]
+    fn only_permanent_redirects_are_followed() {
+        let probe = probe();
+        let follow =
+            |url| follow_permanent_redirects(&probe, url, Duration::ZERO, &mut None).unwrap();
+
+        assert_eq!(
+            follow("https://old.example.com/docs").as_deref(),
+            Some("https://docs.example.com/")
+        );
+        assert_eq!(follow("https://example.com/login"), None);
+        assert_eq!(follow("https://example.com/loop"), None);
+        assert_eq!(follow("https://example.com/"), None);
+    }
+
+    #[test]
+    fn moved_urls_take_their_history_along_and_checks_are_cached() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = SqliteDatabase::open_at(&temp_dir.path().join("test.db")).unwrap();
+        for url in [
+            "https://old.example.com/docs",
+            "https://old.example.com/docs",
+            "https://docs.example.com/",
+            "https://example.com/login",
+        ] {
+            db.add_visit(url, SystemTime::now()).unwrap();
+        }
+        let probe = probe();
+
+        let summary =
+            canonicalize_redirects(&mut db, &probe, 10, 1000, Duration::ZERO, false).unwrap();
+
+        assert_eq!(
+            summary.moved,
+            [(
+                "https://old.example.com/docs".to_string(),
+                "https://docs.example.com/".to_string()
+            )]
+        );
+        assert_eq!(summary.checked, 3);
+        let urls: Vec<String> = db
+            .top_candidates(10)
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.url)
+            .collect();
+        assert_eq!(
+            urls,
+            ["https://docs.example.com/", "https://example.com/login"]
+        );
+
+        let probed = probe.probed.lock().unwrap().len();
+        let again =
+            canonicalize_redirects(&mut db, &probe, 10, 2000, Duration::ZERO, false).unwrap();
+        assert_eq!((again.checked, again.cached), (0, 2));
+        assert_eq!(probe.probed.lock().unwrap().len(), probed);
+    }
+}
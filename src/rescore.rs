@@ -0,0 +1,154 @@
+use anyhow::Result;
+
+use crate::database::Database;
+use crate::rank::RankStrategy;
+use crate::url_classify::{ClassifyOptions, InputType, classify_input_with};
+
+/// How many matches per pattern are compared.
+const COMPARED_MATCHES: usize = 3;
+
+/// The top matches of one pattern under the current ranking and under another.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RescorePreview {
+    pub pattern: String,
+    pub before: Vec<String>,
+    pub after: Vec<String>,
+}
+
+impl RescorePreview {
+    /// Whether `open` would launch a different URL.
+    pub fn changes_winner(&self) -> bool {
+        self.before.first() != self.after.first()
+    }
+}
+
+/// Patterns worth replaying, most recent first: ones typed to open something, and recorded
+/// misses, which may well match by now, up to `count` distinct ones.
+pub fn recent_patterns(db: &dyn Database, count: usize) -> Result<Vec<String>> {
+    let mut typed: Vec<(i64, String)> = db
+        .resolutions(None, usize::MAX)?
+        .into_iter()
+        .map(|resolution| (resolution.resolved_at, resolution.pattern))
+        .chain(
+            db.misses()?
+                .into_iter()
+                .map(|miss| (miss.last_missed, miss.pattern)),
+        )
+        .collect();
+    typed.sort_by_key(|(at, _)| std::cmp::Reverse(*at));
+
+    let mut patterns: Vec<String> = Vec::new();
+    for (_, pattern) in typed {
+        if patterns.len() == count {
+            break;
+        }
+        if !patterns.contains(&pattern) {
+            patterns.push(pattern);
+        }
+    }
+    Ok(patterns)
+}
+
+/// Ranks the matches of each pattern by `before` and by `after` and keeps the top three of
+/// each. Patterns that are full URLs or paths rather than history patterns, or that match
+/// nothing either way, are left out.
+pub fn preview_rescore(
+    db: &dyn Database,
+    patterns: &[String],
+    options: &ClassifyOptions,
+    before: RankStrategy,
+    after: RankStrategy,
+) -> Result<Vec<RescorePreview>> {
+    let top = |segments: &[String], strategy: RankStrategy| -> Result<Vec<String>> {
+        Ok(db
+            .ranked_matches(segments, strategy)?
+            .into_iter()
+            .take(COMPARED_MATCHES)
+            .map(|(url, ..)| url)
+            .collect())
+    };
+
+    let mut previews = Vec::new();
+    for pattern in patterns {
+        let InputType::FuzzyPattern(segments) = classify_input_with(pattern, options) else {
+            continue;
+        };
+        if previews
+            .iter()
+            .any(|preview: &RescorePreview| preview.pattern == *pattern)
+        {
+            continue;
+        }
+
+        let preview = RescorePreview {
+            pattern: pattern.clone(),
+            before: top(&segments, before)?,
+            after: top(&segments, after)?,
+        };
+        if !preview.before.is_empty() {
+            previews.push(preview);
+        }
+    }
+
+    Ok(previews)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::SqliteDatabase;
+    use assert_fs::TempDir;
+    use std::time::{Duration, SystemTime};
+
+    #[test]
+    fn rankings_are_compared_per_pattern() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut db = SqliteDatabase::open_at(&temp_dir.path().join("test.db")).unwrap();
+        let long_ago = SystemTime::now() - Duration::from_secs(30 * 86400);
+        for _ in 0..5 {
+            db.add_visit("https://github.com/rust-lang/rust/issues", long_ago)
+                .unwrap();
+        }
+        db.add_visit(
+            "https://github.com/tokio-rs/tokio/issues",
+            SystemTime::now(),
+        )
+        .unwrap();
+        db.record_miss("gh issues", SystemTime::now()).unwrap();
+        db.record_miss("nothing here", SystemTime::now()).unwrap();
+        db.record_resolution(
+            "gh tokio issues",
+            "https://github.com/tokio-rs/tokio/issues",
+            SystemTime::now() - Duration::from_secs(60),
+        )
+        .unwrap();
+        let patterns = [
+            recent_patterns(&db, 10).unwrap(),
+            vec!["https://example.com/".to_string()],
+        ]
+        .concat();
+
+        let previews = preview_rescore(
+            &db,
+            &patterns,
+            &ClassifyOptions::default(),
+            RankStrategy::Score,
+            RankStrategy::Recency,
+        )
+        .unwrap();
+
+        assert_eq!(previews.len(), 2);
+        assert_eq!(previews[0].pattern, "gh issues");
+        assert_eq!(previews[1].pattern, "gh tokio issues");
+        assert!(!previews[1].changes_winner());
+        assert_eq!(
+            previews[0].before[0],
+            "https://github.com/rust-lang/rust/issues"
+        );
+        assert_eq!(
+            previews[0].after[0],
+            "https://github.com/tokio-rs/tokio/issues"
+        );
+        assert!(previews[0].changes_winner());
+    }
+}
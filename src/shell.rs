@@ -0,0 +1,232 @@
+use anyhow::{Context, Result};
+use clap::builder::{OsStringValueParser, TypedValueParser};
+use std::ffi::OsString;
+use std::process::Command;
+
+use crate::viewers::fill_url_placeholders;
+
+/// Puts back together an address the shell split into several words. Pattern words are
+/// joined with spaces, which separate segments anyway.
+pub fn reconstruct_address(words: &[String]) -> Result<String> {
+    match words {
+        [] => anyhow::bail!("No address given"),
+        [word] => Ok(word.clone()),
+        _ if words.iter().any(|word| word.contains("://")) => anyhow::bail!(
+            "The URL was split into {} arguments, probably at an unquoted space. Quote it: otot open '{}'",
+            words.len(),
+            words.join(" ")
+        ),
+        _ => Ok(words.join(" ")),
+    }
+}
+
+/// A hint for several words that all name files, which is what an unquoted wildcard
+/// expands to. `exists` tells whether a word names a file in the working directory. The
+/// words could still be a pattern, so they are matched anyway.
+pub fn wildcard_hint(words: &[String], exists: impl Fn(&str) -> bool) -> Option<String> {
+    (words.len() > 1 && words.iter().all(|word| exists(word))).then(|| {
+        format!(
+            "hint: the shell may have expanded a wildcard in the pattern into {} file names ({}, ...). Quote the pattern so `*` and `?` reach otot, e.g. otot open 'gh/rust*'",
+            words.len(),
+            words[0]
+        )
+    })
+}
+
+/// A hint for a URL with a query string when otot runs as a background job: an unquoted `&`
+/// in the URL both cut it short and sent the command to the background.
+pub fn background_hint(address: &str, backgrounded: bool) -> Option<String> {
+    (backgrounded && address.contains('?')).then(|| {
+        format!(
+            "hint: otot is running in the background. If the URL contained `&`, the shell cut it off there; quote it: otot open '{}&...'",
+            address
+        )
+    })
+}
+
+/// The parser for text arguments: they are taken as the shell passed them and only then
+/// made UTF-8, replacing what isn't with U+FFFD and saying so. A stray byte in a pattern
+/// thus doesn't stop the command, and arguments parsed as paths are never touched.
+pub fn lossy_text() -> impl TypedValueParser<Value = String> {
+    OsStringValueParser::new().map(|arg: OsString| match arg.into_string() {
+        Ok(text) => text,
+        Err(arg) => {
+            let text = arg.to_string_lossy().into_owned();
+            eprintln!(
+                "otot: argument {:?} is not valid UTF-8; using {:?}",
+                arg, text
+            );
+            text
+        }
+    })
+}
+
+/// Splits a command line into words the way a POSIX shell would for a plain command:
+/// at unquoted whitespace, with single quotes taken literally and backslashes escaping the
+/// next character outside them. Pipes, variables and the like are not interpreted.
+pub fn split_command_line(line: &str) -> Result<Vec<String>> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => words.extend(word.take()),
+            '\'' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => word.push(c),
+                        None => anyhow::bail!("Unterminated ' in command: {}", line),
+                    }
+                }
+            }
+            '"' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\' | '$' | '`')) => word.push(c),
+                            Some(c) => word.extend(['\\', c]),
+                            None => anyhow::bail!("Unterminated \" in command: {}", line),
+                        },
+                        Some(c) => word.push(c),
+                        None => anyhow::bail!("Unterminated \" in command: {}", line),
+                    }
+                }
+            }
+            '\\' => {
+                let escaped = chars.next().context("Command ends in a lone backslash")?;
+                word.get_or_insert_with(String::new).push(escaped);
+            }
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    words.extend(word);
+
+    if words.is_empty() {
+        anyhow::bail!("No command given");
+    }
+    Ok(words)
+}
+
+/// Runs the command `words`, split by `split_command_line`, after `url` was opened, with
+/// the viewers' `{url}` and `{url_encoded}` placeholders filled in. The URL is also passed
+/// as `OTOT_URL`. Each word is one argument however the URL is spelled, so no shell ever
+/// sees it.
+pub fn run_follow_up(words: &[String], url: &str) -> Result<()> {
+    let (program, args) = words.split_first().context("No command given")?;
+    let status = Command::new(fill_url_placeholders(program, url))
+        .args(args.iter().map(|arg| fill_url_placeholders(arg, url)))
+        .env("OTOT_URL", url)
+        .status()
+        .with_context(|| format!("Failed to run '{}'", program))?;
+
+    if !status.success() {
+        anyhow::bail!("'{}' failed after opening {} ({})", program, url, status);
+    }
+    Ok(())
+}
+
+/// Whether this process is a background job of the terminal it is attached to.
+#[cfg(target_os = "linux")]
+pub fn running_in_background() -> bool {
+    let Ok(stat) = std::fs::read_to_string("/proc/self/stat") else {
+        return false;
+    };
+    // The command name may contain spaces, so start after its closing parenthesis:
+    // state ppid pgrp session tty_nr tpgid ...
+    let Some((_, fields)) = stat.rsplit_once(") ") else {
+        return false;
+    };
+    let fields: Vec<&str> = fields.split_whitespace().collect();
+    match (fields.get(2), fields.get(4), fields.get(5)) {
+        (Some(pgrp), Some(tty), Some(tpgid)) => *tty != "0" && pgrp != tpgid,
+        _ => false,
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn running_in_background() -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(words: &[&str]) -> Vec<String> {
+        words.iter().map(|w| w.to_string()).collect()
+    }
+
+    #[test]
+    fn single_word_is_kept_as_is() {
+        let address = reconstruct_address(&words(&["gh/rust"])).unwrap();
+        assert_eq!(address, "gh/rust");
+    }
+
+    #[test]
+    fn pattern_words_are_joined_with_spaces() {
+        let address = reconstruct_address(&words(&["github", "rust", "issues"]));
+        assert_eq!(address.unwrap(), "github rust issues");
+    }
+
+    #[test]
+    fn expanded_wildcard_gets_a_hint_and_is_still_matched() {
+        let expanded = words(&["rust-notes.md", "rust.toml"]);
+
+        let hint = wildcard_hint(&expanded, |_| true).unwrap();
+        assert!(hint.contains("expanded a wildcard"));
+        assert!(hint.contains("rust-notes.md"));
+        assert_eq!(
+            reconstruct_address(&expanded).unwrap(),
+            "rust-notes.md rust.toml"
+        );
+
+        assert_eq!(wildcard_hint(&expanded, |word| word.ends_with(".md")), None);
+        assert_eq!(wildcard_hint(&words(&["rust.toml"]), |_| true), None);
+    }
+
+    #[test]
+    fn split_url_is_reported_with_quoted_suggestion() {
+        let err = reconstruct_address(&words(&["https://example.com/my", "doc"]))
+            .unwrap_err()
+            .to_string();
+
+        assert!(err.contains("otot open 'https://example.com/my doc'"));
+    }
+
+    #[test]
+    fn command_lines_split_like_a_shell() {
+        assert_eq!(
+            split_command_line(r#"notify-send "opened {url}""#).unwrap(),
+            words(&["notify-send", "opened {url}"])
+        );
+        assert_eq!(
+            split_command_line(r#"  echo 'a  "b"' c\ d "e\"f" ''  "#).unwrap(),
+            words(&["echo", "a  \"b\"", "c d", "e\"f", ""])
+        );
+        assert!(split_command_line("echo 'open").is_err());
+        assert!(split_command_line("   ").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn follow_up_gets_the_url_as_one_argument() {
+        let url = "https://example.com/a;b$(c)";
+        let check = words(&["sh", "-c", r#"test "$1" = "$OTOT_URL""#, "sh", "{url}"]);
+        run_follow_up(&check, url).unwrap();
+
+        let err = run_follow_up(&words(&["false"]), url).unwrap_err();
+        assert!(err.to_string().contains("'false' failed after opening"));
+    }
+
+    #[test]
+    fn background_hint_only_for_queries_in_background_jobs() {
+        assert!(background_hint("https://example.com/?a=1", true).is_some());
+        assert!(background_hint("https://example.com/?a=1", false).is_none());
+        assert!(background_hint("https://example.com/", true).is_none());
+    }
+}
@@ -0,0 +1,348 @@
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::config::{OtotConfig, parse_config};
+use crate::database::{
+    Database, HistoryEntry, HistoryFilter, MergeFilter, MergePolicy, MergeSummary, UrlAnnotations,
+};
+use crate::permissions::write_private_file;
+use crate::unix_now;
+
+/// Bump when the bundle layout changes in a way older builds can't read.
+const STATE_FORMAT: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum StatePart {
+    Config,
+    History,
+}
+
+#[derive(Serialize, Deserialize)]
+struct StateBundle {
+    format: u32,
+    otot_version: String,
+    exported_at: i64,
+    /// The config file as written, so importing runs it through the usual upgrade path
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    config: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    history: Option<Vec<ExportedUrl>>,
+}
+
+/// A history entry with its lock, expiry, contexts and title, which bundles from before
+/// these were exported leave out.
+#[derive(Serialize, Deserialize)]
+struct ExportedUrl {
+    #[serde(flatten)]
+    entry: HistoryEntry,
+    #[serde(flatten)]
+    annotations: UrlAnnotations,
+}
+
+/// Read first, so a bundle from a newer otot gets a clear error instead of a parse failure.
+#[derive(Deserialize)]
+struct BundleHeader {
+    format: u32,
+    otot_version: String,
+}
+
+#[derive(Debug, Default)]
+pub struct StateImport {
+    /// The imported config, for the caller to store in place of the current one
+    pub config: Option<OtotConfig>,
+    pub history: Option<MergeSummary>,
+}
+
+fn wanted(parts: &[StatePart], part: StatePart) -> bool {
+    parts.is_empty() || parts.contains(&part)
+}
+
+/// Writes config and/or history (all of it when `parts` is empty) to a single bundle file,
+/// readable only by the current user. Returns the number of URLs exported.
+pub fn export_state(
+    path: &Path,
+    parts: &[StatePart],
+    config: &OtotConfig,
+    db: &dyn Database,
+) -> Result<usize> {
+    let history = if wanted(parts, StatePart::History) {
+        let mut annotations = db.url_annotations()?;
+        let entries = db.history(&HistoryFilter::default())?;
+        Some(
+            entries
+                .into_iter()
+                .map(|entry| ExportedUrl {
+                    annotations: annotations.remove(&entry.url).unwrap_or_default(),
+                    entry,
+                })
+                .collect(),
+        )
+    } else {
+        None
+    };
+    let urls = history.as_ref().map_or(0, Vec::len);
+
+    let bundle = StateBundle {
+        format: STATE_FORMAT,
+        otot_version: env!("CARGO_PKG_VERSION").to_string(),
+        exported_at: unix_now(),
+        config: if wanted(parts, StatePart::Config) {
+            Some(toml::to_string(config).context("Failed to serialize config")?)
+        } else {
+            None
+        },
+        history,
+    };
+
+    write_private_file(path, serde_json::to_string(&bundle)?.as_bytes())?;
+    Ok(urls)
+}
+
+/// Restores the requested parts (every part in the bundle when `parts` is empty). History
+/// is merged into `db` with `policy`, keeping what `filter` allows; the config is returned
+/// for the caller to store.
+pub fn import_state(
+    path: &Path,
+    parts: &[StatePart],
+    db: &mut dyn Database,
+    policy: MergePolicy,
+    filter: &MergeFilter,
+) -> Result<StateImport> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let header: BundleHeader = serde_json::from_str(&text)
+        .with_context(|| format!("{} is not an otot state export", path.display()))?;
+    if header.format > STATE_FORMAT {
+        anyhow::bail!(
+            "{} was exported by otot {} in a newer format ({}); this build reads up to format {}",
+            path.display(),
+            header.otot_version,
+            header.format,
+            STATE_FORMAT
+        );
+    }
+    let bundle: StateBundle = serde_json::from_str(&text)
+        .with_context(|| format!("{} is not an otot state export", path.display()))?;
+
+    for part in parts {
+        let present = match part {
+            StatePart::Config => bundle.config.is_some(),
+            StatePart::History => bundle.history.is_some(),
+        };
+        if !present {
+            anyhow::bail!("{} does not contain {:?}", path.display(), part);
+        }
+    }
+
+    let mut imported = StateImport::default();
+    if wanted(parts, StatePart::Config)
+        && let Some(text) = &bundle.config
+    {
+        let (config, _) = parse_config(text).context("Failed to import config")?;
+        imported.config = Some(config);
+    }
+    if wanted(parts, StatePart::History)
+        && let Some(exported) = &bundle.history
+    {
+        let entries: Vec<HistoryEntry> = exported.iter().map(|url| url.entry.clone()).collect();
+        imported.history = Some(db.merge_entries(&entries, policy, filter)?);
+
+        let annotations: Vec<(String, UrlAnnotations)> = exported
+            .iter()
+            .filter(|url| url.annotations != UrlAnnotations::default())
+            .filter(|url| filter.allows(&url.entry.url, url.entry.last_accessed))
+            .map(|url| (url.entry.url.clone(), url.annotations.clone()))
+            .collect();
+        db.restore_annotations(&annotations)?;
+    }
+
+    Ok(imported)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::SqliteDatabase;
+    use assert_fs::TempDir;
+    use std::time::{Duration, SystemTime};
+
+    fn source_db(temp_dir: &TempDir) -> SqliteDatabase {
+        let mut db = SqliteDatabase::open_at(&temp_dir.path().join("source.db")).unwrap();
+        let at = |secs| SystemTime::UNIX_EPOCH + Duration::from_secs(secs);
+        db.add_visit("https://github.com/rust-lang/rust", at(1000))
+            .unwrap();
+        db.add_visit("https://example.com/docs", at(2000)).unwrap();
+        db
+    }
+
+    #[test]
+    fn export_then_import_restores_config_and_history() {
+        let temp_dir = TempDir::new().unwrap();
+        let bundle = temp_dir.path().join("otot-state.json");
+        let mut config = OtotConfig::default();
+        config.browser.preferred = Some("firefox".to_string());
+
+        let exported = export_state(&bundle, &[], &config, &source_db(&temp_dir)).unwrap();
+
+        let mut target = SqliteDatabase::open_at(&temp_dir.path().join("target.db")).unwrap();
+        let imported = import_state(
+            &bundle,
+            &[],
+            &mut target,
+            MergePolicy::Newest,
+            &MergeFilter::default(),
+        )
+        .unwrap();
+
+        assert_eq!(exported, 2);
+        assert_eq!(
+            imported.config.unwrap().browser.preferred.as_deref(),
+            Some("firefox")
+        );
+        assert_eq!(imported.history.unwrap().added, 2);
+        assert_eq!(target.history(&HistoryFilter::default()).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn locks_expiries_contexts_and_titles_survive_a_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let bundle = temp_dir.path().join("otot-state.json");
+        let mut source = source_db(&temp_dir);
+        let pinned = "https://github.com/rust-lang/rust";
+        source.set_locked(pinned, true).unwrap();
+        source.set_expiry(pinned, 4_000_000_000).unwrap();
+        source.set_title(pinned, "The Rust language").unwrap();
+        source.set_active_context(Some("work")).unwrap();
+        source.add_visit(pinned, SystemTime::now()).unwrap();
+        export_state(&bundle, &[], &OtotConfig::default(), &source).unwrap();
+
+        let mut target = SqliteDatabase::open_at(&temp_dir.path().join("target.db")).unwrap();
+        import_state(
+            &bundle,
+            &[],
+            &mut target,
+            MergePolicy::Newest,
+            &MergeFilter::default(),
+        )
+        .unwrap();
+
+        assert_eq!(target.locked_urls().unwrap(), [pinned]);
+        assert_eq!(
+            target.url_annotations().unwrap()[pinned],
+            UrlAnnotations {
+                locked: true,
+                expires_at: Some(4_000_000_000),
+                contexts: vec!["work".to_string()],
+                title: Some("The Rust language".to_string()),
+            }
+        );
+        assert_eq!(
+            target.prune_by_url_pattern("rust-lang").unwrap(),
+            0,
+            "a locked URL stays locked after the import"
+        );
+    }
+
+    #[test]
+    fn bundles_without_annotations_still_import() {
+        let temp_dir = TempDir::new().unwrap();
+        let bundle = temp_dir.path().join("otot-state.json");
+        std::fs::write(
+            &bundle,
+            r#"{"format": 1, "otot_version": "0.3.0", "exported_at": 0, "history": [
+                {"url": "https://example.com/", "score": 1.0, "last_accessed": 1000,
+                 "first_seen": 1000, "updated_at": null}
+            ]}"#,
+        )
+        .unwrap();
+
+        let mut target = SqliteDatabase::open_at(&temp_dir.path().join("target.db")).unwrap();
+        let imported = import_state(
+            &bundle,
+            &[],
+            &mut target,
+            MergePolicy::Newest,
+            &MergeFilter::default(),
+        )
+        .unwrap();
+
+        assert_eq!(imported.history.unwrap().added, 1);
+        assert!(target.url_annotations().unwrap().is_empty());
+    }
+
+    #[test]
+    fn partial_import_skips_other_parts() {
+        let temp_dir = TempDir::new().unwrap();
+        let bundle = temp_dir.path().join("otot-state.json");
+        export_state(&bundle, &[], &OtotConfig::default(), &source_db(&temp_dir)).unwrap();
+
+        let mut target = SqliteDatabase::open_at(&temp_dir.path().join("target.db")).unwrap();
+        let imported = import_state(
+            &bundle,
+            &[StatePart::Config],
+            &mut target,
+            MergePolicy::Newest,
+            &MergeFilter::default(),
+        )
+        .unwrap();
+
+        assert!(imported.config.is_some());
+        assert!(imported.history.is_none());
+        assert!(
+            target
+                .history(&HistoryFilter::default())
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn requesting_a_part_missing_from_the_bundle_fails() {
+        let temp_dir = TempDir::new().unwrap();
+        let bundle = temp_dir.path().join("otot-state.json");
+        export_state(
+            &bundle,
+            &[StatePart::Config],
+            &OtotConfig::default(),
+            &source_db(&temp_dir),
+        )
+        .unwrap();
+
+        let mut target = SqliteDatabase::open_at(&temp_dir.path().join("target.db")).unwrap();
+        let err = import_state(
+            &bundle,
+            &[StatePart::History],
+            &mut target,
+            MergePolicy::Newest,
+            &MergeFilter::default(),
+        )
+        .unwrap_err();
+
+        assert!(err.to_string().contains("does not contain History"));
+    }
+
+    #[test]
+    fn bundle_from_newer_format_is_rejected() {
+        let temp_dir = TempDir::new().unwrap();
+        let bundle = temp_dir.path().join("otot-state.json");
+        std::fs::write(
+            &bundle,
+            r#"{"format": 99, "otot_version": "9.0.0", "exported_at": 0, "archive": "zstd"}"#,
+        )
+        .unwrap();
+
+        let mut target = SqliteDatabase::open_at(&temp_dir.path().join("target.db")).unwrap();
+        let err = import_state(
+            &bundle,
+            &[],
+            &mut target,
+            MergePolicy::Newest,
+            &MergeFilter::default(),
+        )
+        .unwrap_err();
+
+        assert!(err.to_string().contains("newer format"));
+    }
+}
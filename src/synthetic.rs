@@ -0,0 +1,257 @@
+use std::time::{Duration, SystemTime};
+
+const DOMAINS: &[&str] = &[
+    "github.com",
+    "google.com",
+    "stackoverflow.com",
+    "docs.rs",
+    "crates.io",
+    "youtube.com",
+    "en.wikipedia.org",
+    "news.ycombinator.com",
+    "reddit.com",
+    "gitlab.com",
+    "developer.mozilla.org",
+    "doc.rust-lang.org",
+    "mail.google.com",
+    "calendar.google.com",
+    "jira.example.com",
+    "confluence.example.com",
+    "grafana.example.com",
+    "ci.example.com",
+    "linear.app",
+    "notion.so",
+    "figma.com",
+    "aws.amazon.com",
+    "console.cloud.google.com",
+    "portal.azure.com",
+    "twitter.com",
+    "medium.com",
+    "dev.to",
+    "lobste.rs",
+    "arxiv.org",
+    "npmjs.com",
+    "pypi.org",
+    "hub.docker.com",
+];
+
+const WORDS: &[&str] = &[
+    "rust",
+    "issues",
+    "pull",
+    "docs",
+    "api",
+    "settings",
+    "search",
+    "wiki",
+    "blob",
+    "main",
+    "src",
+    "tree",
+    "dashboard",
+    "projects",
+    "browse",
+    "board",
+    "release",
+    "notes",
+    "guide",
+    "reference",
+    "std",
+    "tokio",
+    "serde",
+    "clap",
+    "config",
+    "users",
+    "admin",
+    "billing",
+    "team",
+    "builds",
+    "logs",
+    "metrics",
+    "alerts",
+    "deploy",
+    "review",
+    "compare",
+    "commits",
+    "actions",
+    "runs",
+    "questions",
+    "tagged",
+    "watch",
+    "playlist",
+    "r",
+    "comments",
+    "item",
+    "article",
+    "abs",
+    "package",
+    "crate",
+    "latest",
+    "index",
+    "overview",
+    "spec",
+    "tutorial",
+    "faq",
+    "changelog",
+];
+
+const MAX_DEPTH: u64 = 6;
+const MAX_EXTRA_VISITS: u64 = 40;
+const HISTORY_SPAN_SECS: u64 = 2 * 365 * 24 * 3600;
+
+/// Deterministic generator of realistic-looking browsing history for benchmarks and load
+/// testing: a few domains get most of the traffic, paths are mostly shallow but sometimes
+/// deep, and visit counts and recency are skewed the way real histories are.
+pub struct HistoryGenerator {
+    state: u64,
+    domain_weights: Vec<f64>,
+    word_weights: Vec<f64>,
+}
+
+impl HistoryGenerator {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            state: seed,
+            domain_weights: zipf_cdf(DOMAINS.len(), 1.1),
+            word_weights: zipf_cdf(WORDS.len(), 1.0),
+        }
+    }
+
+    /// Generates `urls` distinct URLs and returns every visit to them, oldest first.
+    pub fn visits(&mut self, urls: usize, now: SystemTime) -> Vec<(String, SystemTime)> {
+        let mut seen = std::collections::HashSet::with_capacity(urls);
+        let mut visits = Vec::with_capacity(urls * 2);
+
+        while seen.len() < urls {
+            let url = self.url(seen.len());
+            if !seen.insert(url.clone()) {
+                continue;
+            }
+
+            let extra_visits = self.zipf_small(MAX_EXTRA_VISITS);
+            for _ in 0..=extra_visits {
+                // Squaring a uniform sample skews visits towards the recent past
+                let age = (self.unit().powi(2) * HISTORY_SPAN_SECS as f64) as u64;
+                visits.push((url.clone(), now - Duration::from_secs(age)));
+            }
+        }
+
+        visits.sort_by_key(|(_, at)| *at);
+        visits
+    }
+
+    fn url(&mut self, serial: usize) -> String {
+        let target = self.unit();
+        let domain = DOMAINS[pick(&self.domain_weights, target)];
+        let depth = self.zipf_small(MAX_DEPTH);
+
+        let mut url = format!("https://{}", domain);
+        for level in 0..depth {
+            url.push('/');
+            if level + 1 == depth && self.unit() < 0.3 {
+                // Issue numbers, ticket ids and the like keep deep URLs distinct
+                url.push_str(&(self.next() % 100_000).to_string());
+            } else {
+                let target = self.unit();
+                url.push_str(WORDS[pick(&self.word_weights, target)]);
+            }
+        }
+
+        if depth == 0 && serial > 0 {
+            url.push_str(&format!("/?ref={}", serial));
+        }
+        url
+    }
+
+    /// Small values are much more likely: 0 about half the time, `max` rarely.
+    fn zipf_small(&mut self, max: u64) -> u64 {
+        let mut value = 0;
+        while value < max && self.unit() < 0.5 {
+            value += 1;
+        }
+        value
+    }
+
+    fn unit(&mut self) -> f64 {
+        (self.next() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    // splitmix64
+    fn next(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
+
+/// Index of the bucket `target` (in `0..1`) falls into.
+fn pick(cdf: &[f64], target: f64) -> usize {
+    cdf.partition_point(|&p| p < target).min(cdf.len() - 1)
+}
+
+fn zipf_cdf(n: usize, exponent: f64) -> Vec<f64> {
+    let weights: Vec<f64> = (1..=n)
+        .map(|rank| 1.0 / (rank as f64).powf(exponent))
+        .collect();
+    let total: f64 = weights.iter().sum();
+
+    let mut cumulative = 0.0;
+    weights
+        .iter()
+        .map(|w| {
+            cumulative += w / total;
+            cumulative
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::{HashMap, HashSet};
+
+    #[test]
+    fn generates_requested_number_of_distinct_urls() {
+        let visits = HistoryGenerator::new(7).visits(500, SystemTime::now());
+
+        let distinct: HashSet<&str> = visits.iter().map(|(url, _)| url.as_str()).collect();
+        assert_eq!(distinct.len(), 500);
+        assert!(visits.len() >= 500);
+    }
+
+    #[test]
+    fn same_seed_generates_same_history() {
+        let now = SystemTime::now();
+        let a = HistoryGenerator::new(42).visits(100, now);
+        let b = HistoryGenerator::new(42).visits(100, now);
+
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn popular_domains_dominate() {
+        let visits = HistoryGenerator::new(1).visits(2000, SystemTime::now());
+        let mut per_domain: HashMap<String, usize> = HashMap::new();
+        for (url, _) in &visits {
+            let host = url::Url::parse(url)
+                .unwrap()
+                .host_str()
+                .unwrap()
+                .to_string();
+            *per_domain.entry(host).or_default() += 1;
+        }
+
+        assert!(per_domain["github.com"] > per_domain.get("hub.docker.com").copied().unwrap_or(0));
+    }
+
+    #[test]
+    fn visits_are_ordered_and_in_the_past() {
+        let now = SystemTime::now();
+        let visits = HistoryGenerator::new(3).visits(200, now);
+
+        assert!(visits.windows(2).all(|w| w[0].1 <= w[1].1));
+        assert!(visits.iter().all(|(_, at)| *at <= now));
+    }
+}
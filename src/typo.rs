@@ -0,0 +1,195 @@
+use anyhow::Result;
+use log::info;
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::database::Database;
+use crate::prompt::{ask_yes_no, interactive};
+use crate::url_classify::{InputType, classify_input, clean_pasted_address};
+
+/// Summed score a domain needs before lookalikes of it are treated as likely typos.
+const TYPO_GUARD_MIN_SCORE: f64 = 3.0;
+
+/// For a bare domain typed without a scheme (`gihub.com/rust`) that has never been visited
+/// but is one edit away from a frequently visited domain, returns the URL with the known
+/// domain swapped in (`https://github.com/rust`). A leading `www.` is ignored on both sides.
+pub fn suggest_typo_correction(db: &dyn Database, address: &str) -> Result<Option<String>> {
+    if clean_pasted_address(address).contains("://") {
+        return Ok(None);
+    }
+    let InputType::FullUrl(mut url) = classify_input(address) else {
+        return Ok(None);
+    };
+    let Some(typed) = url.domain().map(str::to_string) else {
+        return Ok(None);
+    };
+
+    let typed = without_www(&typed);
+
+    let mut lookups = one_edit_variants(typed);
+    lookups.insert(typed.to_string());
+    let with_www: Vec<String> = lookups
+        .iter()
+        .map(|domain| format!("www.{domain}"))
+        .collect();
+    lookups.extend(with_www);
+
+    let domains = db.domain_scores(&lookups)?;
+    if domains
+        .iter()
+        .any(|(domain, _)| without_www(domain) == typed)
+    {
+        return Ok(None);
+    }
+
+    let mut totals: BTreeMap<&str, f64> = BTreeMap::new();
+    for (domain, score) in &domains {
+        *totals.entry(without_www(domain)).or_default() += score;
+    }
+    let Some((known, _)) = domains.iter().find(|(domain, _)| {
+        let bare = without_www(domain);
+        totals[bare] >= TYPO_GUARD_MIN_SCORE && bare.contains('.')
+    }) else {
+        return Ok(None);
+    };
+
+    info!("{:?} looks like a typo of {:?}", typed, known);
+    url.set_host(Some(known))?;
+    Ok(Some(url.to_string()))
+}
+
+fn without_www(domain: &str) -> &str {
+    domain.strip_prefix("www.").unwrap_or(domain)
+}
+
+/// Characters a hostname is made of once the URL parser has lowercased and punycoded it.
+const DOMAIN_CHARS: &str = "abcdefghijklmnopqrstuvwxyz0123456789-.";
+
+/// Every domain that becomes `domain` with one insertion, deletion, substitution or swap of
+/// two adjacent characters, so the known ones can be looked up rather than searched for.
+fn one_edit_variants(domain: &str) -> BTreeSet<String> {
+    let chars: Vec<char> = domain.chars().collect();
+    let variant = |parts: &[&[char]]| parts.concat().into_iter().collect::<String>();
+
+    let mut variants = BTreeSet::new();
+    for i in 0..=chars.len() {
+        let (before, after) = chars.split_at(i);
+        for c in DOMAIN_CHARS.chars() {
+            variants.insert(variant(&[before, &[c], after]));
+        }
+        if let Some((_, rest)) = after.split_first() {
+            variants.insert(variant(&[before, rest]));
+            for c in DOMAIN_CHARS.chars() {
+                variants.insert(variant(&[before, &[c], rest]));
+            }
+        }
+        if let [a, b, rest @ ..] = after {
+            variants.insert(variant(&[before, &[*b, *a], rest]));
+        }
+    }
+    variants.remove(domain);
+    variants
+}
+
+/// Asks whether to open the frequently visited domain instead when `address` looks like a
+/// typo of it. Without a terminal to ask on, refuses rather than risk a typosquat.
+pub fn confirm_typo_correction(db: &dyn Database, address: &str) -> Result<Option<String>> {
+    let Some(suggestion) = suggest_typo_correction(db, address)? else {
+        return Ok(None);
+    };
+
+    if !interactive() {
+        anyhow::bail!(
+            "'{}' looks like a typo of {}, which you visit often. Run `otot open {}`, or pass --no-typo-check to open '{}' anyway",
+            address,
+            suggestion,
+            suggestion,
+            address
+        );
+    }
+
+    let question = format!(
+        "'{}' looks like a typo of a site you visit often. Open {} instead? [y/N] ",
+        address, suggestion
+    );
+    Ok(ask_yes_no(&question)?.then_some(suggestion))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::SqliteDatabase;
+    use assert_fs::TempDir;
+    use std::time::SystemTime;
+
+    fn create_temp_db() -> (TempDir, SqliteDatabase) {
+        let temp_dir = TempDir::new().unwrap();
+        let db = SqliteDatabase::open_at(&temp_dir.path().join("test.db")).unwrap();
+        (temp_dir, db)
+    }
+
+    fn visit_times(db: &mut SqliteDatabase, url: &str, times: usize) {
+        for _ in 0..times {
+            db.add_visit(url, SystemTime::now()).unwrap();
+        }
+    }
+
+    #[test]
+    fn one_edit_variants_cover_common_typos() {
+        let variants = one_edit_variants("github.com");
+        assert!(variants.contains("gihub.com"));
+        assert!(variants.contains("githubb.com"));
+        assert!(variants.contains("gitjub.com"));
+        assert!(variants.contains("githbu.com"));
+        assert!(!variants.contains("github.com"));
+        assert!(!variants.contains("gitlab.com"));
+    }
+
+    #[test]
+    fn typo_of_frequent_domain_is_corrected_keeping_the_path() {
+        let (_temp_dir, mut db) = create_temp_db();
+        visit_times(&mut db, "https://github.com/rust-lang/rust", 3);
+
+        assert_eq!(
+            suggest_typo_correction(&db, "gihub.com/rust-lang").unwrap(),
+            Some("https://github.com/rust-lang".to_string())
+        );
+    }
+
+    #[test]
+    fn typo_guard_leaves_other_addresses_alone() {
+        let (_temp_dir, mut db) = create_temp_db();
+        visit_times(&mut db, "https://github.com/rust-lang/rust", 3);
+        visit_times(&mut db, "https://gitlab.com/", 1);
+        visit_times(&mut db, "https://example.org/", 1);
+
+        // Visited before, explicitly typed scheme, a pattern, or a rarely visited lookalike
+        assert_eq!(suggest_typo_correction(&db, "gitlab.com").unwrap(), None);
+        assert_eq!(
+            suggest_typo_correction(&db, "https://gihub.com").unwrap(),
+            None
+        );
+        assert_eq!(suggest_typo_correction(&db, "gh/rust").unwrap(), None);
+        assert_eq!(suggest_typo_correction(&db, "exampel.org").unwrap(), None);
+    }
+
+    #[test]
+    fn typo_guard_ignores_a_leading_www() {
+        let (_temp_dir, mut db) = create_temp_db();
+        visit_times(&mut db, "https://www.github.com/rust-lang", 2);
+        visit_times(&mut db, "https://github.com/rust-lang", 1);
+
+        assert_eq!(
+            suggest_typo_correction(&db, "www.github.com").unwrap(),
+            None
+        );
+        assert_eq!(suggest_typo_correction(&db, "github.com").unwrap(), None);
+        assert_eq!(
+            suggest_typo_correction(&db, "www.gihub.com/rust-lang").unwrap(),
+            Some("https://www.github.com/rust-lang".to_string())
+        );
+        assert_eq!(
+            suggest_typo_correction(&db, "gihub.com").unwrap(),
+            Some("https://www.github.com/".to_string())
+        );
+    }
+}
@@ -0,0 +1,180 @@
+use anyhow::{Context, Result};
+use std::time::Duration;
+
+/// Duration units, largest first. Months and years are 30 and 365 days.
+const DURATION_UNITS: &[(&str, u64, &str)] = &[
+    ("y", 31536000, "years"),
+    ("m", 2592000, "months"),
+    ("w", 604800, "weeks"),
+    ("d", 86400, "days"),
+    ("h", 3600, "hours"),
+    ("min", 60, "minutes"),
+    ("s", 1, "seconds"),
+];
+
+/// Size units, powers of 1024 as the operating system reports file sizes.
+const SIZE_UNITS: &[(&str, u64)] = &[
+    ("TB", 1 << 40),
+    ("GB", 1 << 30),
+    ("MB", 1 << 20),
+    ("KB", 1 << 10),
+    ("B", 1),
+];
+
+/// Splits `30d` or `50 MB` into its number and unit.
+fn split_quantity(s: &str) -> (&str, &str) {
+    let s = s.trim();
+    let unit_start = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (number, unit) = s.split_at(unit_start);
+    (number, unit.trim())
+}
+
+/// A span such as `45min`, `6h`, `90d`, `2w`, `6m` or `1y`.
+pub fn parse_duration(s: &str) -> Result<Duration> {
+    if s.trim().is_empty() {
+        anyhow::bail!("Duration cannot be empty");
+    }
+
+    let (number, unit) = split_quantity(s);
+    let number: u64 = number
+        .parse()
+        .with_context(|| format!("Invalid number in duration '{}', e.g. 90d", s.trim()))?;
+    let Some((_, unit_secs, _)) = DURATION_UNITS.iter().find(|(name, _, _)| *name == unit) else {
+        anyhow::bail!(
+            "Invalid duration unit in '{}'. Use {}",
+            s.trim(),
+            DURATION_UNITS
+                .iter()
+                .map(|(name, _, meaning)| format!("{} ({})", name, meaning))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    };
+    let secs = number
+        .checked_mul(*unit_secs)
+        .with_context(|| format!("Duration '{}' is too long", s.trim()))?;
+
+    Ok(Duration::from_secs(secs))
+}
+
+/// Formats a duration in the largest unit that divides it, e.g. `2w` or `36h`. Months are
+/// skipped, since 30 days reads better as `30d` than as `1m`.
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    DURATION_UNITS
+        .iter()
+        .filter(|(name, _, _)| *name != "m")
+        .find(|(_, unit_secs, _)| secs > 0 && secs.is_multiple_of(*unit_secs))
+        .map_or_else(
+            || "0s".to_string(),
+            |(name, unit_secs, _)| format!("{}{}", secs / unit_secs, name),
+        )
+}
+
+/// Formats a span of seconds compactly to the minute, e.g. `45min` or `2h 5min`, in units
+/// `parse_duration` reads back.
+pub fn format_duration_short(secs: i64) -> String {
+    let minutes = secs.max(0) / 60;
+    match minutes {
+        0..=59 => format!("{}min", minutes),
+        _ if minutes % 60 == 0 => format!("{}h", minutes / 60),
+        _ => format!("{}h {}min", minutes / 60, minutes % 60),
+    }
+}
+
+/// A whole number of bytes, kilobytes and so on: `512KB`, `50 MB`, `2GB`. A plain number
+/// is bytes; units are case-insensitive and may be written `MiB`.
+pub fn parse_size(s: &str) -> Result<u64> {
+    let (number, unit) = split_quantity(s);
+    let number: u64 = number
+        .parse()
+        .with_context(|| format!("Invalid size '{}', e.g. 50MB", s.trim()))?;
+    let unit = unit.to_ascii_uppercase().replace("IB", "B");
+    let unit = if unit.is_empty() { "B" } else { unit.as_str() };
+    let Some((_, unit_bytes)) = SIZE_UNITS.iter().find(|(name, _)| *name == unit) else {
+        anyhow::bail!(
+            "Invalid size unit in '{}'. Use B, KB, MB, GB or TB",
+            s.trim()
+        );
+    };
+
+    number
+        .checked_mul(*unit_bytes)
+        .with_context(|| format!("Size '{}' is too large", s.trim()))
+}
+
+/// Formats a size in the largest unit it fills, e.g. `100MB` or `1.5GB`.
+pub fn format_size(bytes: u64) -> String {
+    let (name, unit_bytes) = SIZE_UNITS
+        .iter()
+        .find(|(_, unit_bytes)| bytes >= *unit_bytes)
+        .unwrap_or(&("B", 1));
+    if bytes.is_multiple_of(*unit_bytes) {
+        format!("{}{}", bytes / unit_bytes, name)
+    } else {
+        format!("{:.1}{}", bytes as f64 / *unit_bytes as f64, name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn durations_accept_every_unit_and_surrounding_space() {
+        assert_eq!(parse_duration(" 90s ").unwrap(), Duration::from_secs(90));
+        assert_eq!(parse_duration("45min").unwrap(), Duration::from_secs(2700));
+        assert_eq!(parse_duration("6h").unwrap(), Duration::from_secs(21600));
+        assert_eq!(parse_duration("90d").unwrap(), Duration::from_secs(7776000));
+    }
+
+    #[test]
+    fn duration_errors_name_the_input() {
+        let unit = parse_duration("30x").unwrap_err().to_string();
+        assert!(unit.contains("'30x'"));
+        assert!(unit.contains("min (minutes)"));
+
+        let number = format!("{:#}", parse_duration("d").unwrap_err());
+        assert!(number.contains("Invalid number in duration 'd'"));
+        assert!(parse_duration("99999999999999y").is_err());
+    }
+
+    #[test]
+    fn durations_format_in_their_largest_exact_unit() {
+        assert_eq!(format_duration(Duration::from_secs(14 * 86400)), "2w");
+        assert_eq!(format_duration(Duration::from_secs(30 * 86400)), "30d");
+        assert_eq!(format_duration(Duration::from_secs(36 * 3600)), "36h");
+        assert_eq!(format_duration(Duration::from_secs(31536000)), "1y");
+        assert_eq!(format_duration(Duration::ZERO), "0s");
+    }
+
+    #[test]
+    fn short_durations_spell_minutes_as_min() {
+        assert_eq!(format_duration_short(45 * 60 + 30), "45min");
+        assert_eq!(format_duration_short(2 * 3600), "2h");
+        assert_eq!(format_duration_short(2 * 3600 + 5 * 60), "2h 5min");
+        assert_eq!(format_duration_short(-5), "0min");
+        assert_eq!(
+            parse_duration(&format_duration_short(45 * 60)).unwrap(),
+            Duration::from_secs(45 * 60)
+        );
+    }
+
+    #[test]
+    fn sizes_accept_common_spellings() {
+        assert_eq!(parse_size("50MB").unwrap(), 50 * 1024 * 1024);
+        assert_eq!(parse_size("50 mib").unwrap(), 50 * 1024 * 1024);
+        assert_eq!(parse_size("2GB").unwrap(), 2 << 30);
+        assert_eq!(parse_size("4096").unwrap(), 4096);
+        assert!(parse_size("50 parsecs").is_err());
+        assert!(parse_size("MB").is_err());
+    }
+
+    #[test]
+    fn sizes_format_in_their_largest_unit() {
+        assert_eq!(format_size(100 << 20), "100MB");
+        assert_eq!(format_size(3 << 29), "1.5GB");
+        assert_eq!(format_size(512), "512B");
+        assert_eq!(format_size(0), "0B");
+    }
+}
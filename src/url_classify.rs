@@ -1,17 +1,162 @@
-use url::Url;
+use anyhow::Result;
+use percent_encoding::percent_decode_str;
+use publicsuffix::{List, Psl};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::net::Ipv6Addr;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+use unicode_normalization::UnicodeNormalization;
+use url::{Host, Url};
+
+/// The Public Suffix List, bundled so reading an address never touches the network. Parsed
+/// on first use, since most patterns are rejected as URLs before it is needed.
+static PUBLIC_SUFFIXES: LazyLock<List> = LazyLock::new(|| {
+    include_str!("../data/public_suffix_list.dat")
+        .parse()
+        .expect("bundled public suffix list is valid")
+});
+
+/// Schemes opened without being listed in `classify.schemes`.
+const BUILTIN_SCHEMES: &[&str] = &["http", "https", "file", "mailto", "ssh"];
+
+/// Top-level names reserved or commonly used on private networks, which the Public Suffix
+/// List leaves out.
+const PRIVATE_SUFFIXES: &[&str] = &["corp", "home", "internal", "lan", "local", "test"];
 
 #[derive(Debug, PartialEq)]
 pub enum InputType {
     FullUrl(Url),
     FuzzyPattern(Vec<String>),
+    /// An absolute path to a local file or directory
+    LocalPath(PathBuf),
+}
+
+/// Characters that split a typed pattern into segments. `/` and whitespace always do.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Separators {
+    chars: Vec<char>,
+}
+
+impl Default for Separators {
+    fn default() -> Self {
+        Self { chars: vec!['/'] }
+    }
+}
+
+impl Separators {
+    /// Parses a set like `"/ ."`. Letters, digits, `:` (ports) and `%` (escapes) can't be
+    /// separators.
+    pub fn parse(spec: &str) -> Result<Self> {
+        let mut separators = Self::default();
+        for c in spec.chars().filter(|c| !c.is_whitespace()) {
+            if c.is_alphanumeric() || c == ':' || c == '%' {
+                anyhow::bail!("'{}' can't be a segment separator", c);
+            }
+            if !separators.chars.contains(&c) {
+                separators.chars.push(c);
+            }
+        }
+        Ok(separators)
+    }
+
+    fn is_separator(&self, c: char) -> bool {
+        c.is_whitespace() || self.chars.contains(&c)
+    }
+
+    /// Splits a pattern into its raw, non-empty segments.
+    pub fn split<'a>(&self, pattern: &'a str) -> impl Iterator<Item = &'a str> {
+        pattern
+            .split(|c: char| self.is_separator(c))
+            .filter(|s| !s.is_empty())
+    }
+
+    /// Whether `address` uses a separator that would never appear in a bare domain typed
+    /// without a scheme, so it has to be a pattern. With `.` configured, a dotted address is
+    /// only a domain if it also has a path or port, e.g. `github.com/rust`.
+    fn forces_pattern(&self, address: &str) -> bool {
+        address.chars().any(|c| match c {
+            '/' => false,
+            '.' => self.chars.contains(&'.') && !address.contains(['/', ':']),
+            c => self.is_separator(c),
+        })
+    }
+}
+
+/// The user's settings that decide how a typed address is read.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClassifyOptions {
+    pub separators: Separators,
+    /// Host names without a public suffix, e.g. `wiki` or `build.acme`, that are opened as
+    /// sites instead of matched as patterns
+    pub known_hosts: Vec<String>,
+    /// Schemes allowed on top of `BUILTIN_SCHEMES`, e.g. `slack` or `vscode`
+    pub schemes: Vec<String>,
+    /// The scheme, `http` or `https`, for addresses typed without one whose host matches a
+    /// pattern, e.g. `*.internal`. These hosts are opened as sites too.
+    pub host_schemes: BTreeMap<String, String>,
+}
+
+impl ClassifyOptions {
+    /// The configured scheme for `host`, from its most specific matching pattern.
+    fn scheme_for_host(&self, host: &str) -> Option<&str> {
+        self.host_schemes
+            .iter()
+            .filter(|(pattern, _)| host_matches(host, pattern))
+            .max_by_key(|(pattern, _)| pattern.trim_start_matches("*.").len())
+            .map(|(_, scheme)| scheme.as_str())
+    }
+
+    fn allows_scheme(&self, scheme: &str) -> bool {
+        BUILTIN_SCHEMES
+            .iter()
+            .copied()
+            .chain(self.schemes.iter().map(String::as_str))
+            .any(|allowed| allowed.eq_ignore_ascii_case(scheme))
+    }
+}
+
+impl From<Separators> for ClassifyOptions {
+    fn from(separators: Separators) -> Self {
+        Self {
+            separators,
+            ..Default::default()
+        }
+    }
 }
 
 pub fn classify_input(address: &str) -> InputType {
+    classify_input_with(address, &ClassifyOptions::default())
+}
+
+pub fn classify_input_with(address: &str, options: &ClassifyOptions) -> InputType {
+    let address = clean_pasted_address(address);
+    let separators = &options.separators;
+
     if address.contains("://")
         && let Ok(url) = Url::parse(address)
     {
         return InputType::FullUrl(url);
     }
+    if let Some(path) = local_path(address) {
+        return InputType::LocalPath(path);
+    }
+
+    // `mailto:` and the like have no `//`, and would otherwise read as a host with a port
+    if let Some((scheme, _)) = address.split_once(':')
+        && !matches!(scheme, "http" | "https" | "file")
+        && options.allows_scheme(scheme)
+        && let Ok(url) = Url::parse(address)
+    {
+        return InputType::FullUrl(url);
+    }
+
+    // A bare IPv6 address only parses as a host in brackets
+    if let Ok(ip) = address.parse::<Ipv6Addr>()
+        && let Ok(url) = Url::parse(&format!("http://[{}]/", ip))
+    {
+        return InputType::FullUrl(url);
+    }
 
     let inferred_scheme = if address.contains(':') {
         "http"
@@ -19,24 +164,536 @@ pub fn classify_input(address: &str) -> InputType {
         "https"
     };
 
+    // Launchers often pass the whole query as one argument ("github rust issues"); a bare
+    // address with inner whitespace is never a URL, so go straight to pattern matching.
     let with_scheme = format!("{}://{}", inferred_scheme, address);
-    if let Ok(url) = Url::parse(&with_scheme) {
-        // XXX: for now, we're assuming that, if the user didn't input a scheme, we can differentiate between a fuzzy pattern
-        //   and a domain that just needs https prepended by the presence of a '.'
-        if url.host_str().is_some_and(|h| h.contains('.')) || url.port().is_some() {
+    if !address.contains(char::is_whitespace)
+        && !separators.forces_pattern(address)
+        && let Ok(mut url) = Url::parse(&with_scheme)
+    {
+        // Development servers and devices addressed by IP rarely serve https, unless the
+        // host's scheme is configured
+        let local = is_local_host(&url);
+        let configured = url
+            .host_str()
+            .and_then(|host| options.scheme_for_host(host));
+        if let Some(scheme) = configured.or(local.then_some("http")) {
+            let _ = url.set_scheme(scheme);
+        }
+        // Without a scheme, a dotted host is only a domain if it ends in a real suffix, so
+        // `notes.txt` or `v1.2` stay patterns
+        let known = url.host_str().is_some_and(|host| {
+            known_suffix(host).is_some()
+                || options
+                    .known_hosts
+                    .iter()
+                    .any(|known| host_matches(host, known))
+        });
+        if local || known || configured.is_some() || url.port().is_some() {
             return InputType::FullUrl(url);
         }
     }
 
-    InputType::FuzzyPattern(
-        address
-            .split('/')
-            .filter(|s| !s.is_empty())
-            .map(|s| s.to_lowercase())
-            .collect(),
+    InputType::FuzzyPattern(separators.split(address).map(normalize_segment).collect())
+}
+
+/// The absolute path `address` names, with `~` expanded, when it is written as a path:
+/// starting with `./`, `../` or `~/`, or an absolute path that exists. Absolute paths that
+/// don't exist are left to pattern matching, where a leading `/` is just a separator.
+fn local_path(address: &str) -> Option<PathBuf> {
+    let path = if let Some(rest) = address.strip_prefix("~/") {
+        dirs::home_dir()?.join(rest)
+    } else if address == "~" {
+        dirs::home_dir()?
+    } else if address.starts_with("./") || address.starts_with("../") {
+        std::env::current_dir().ok()?.join(address)
+    } else if Path::new(address).is_absolute() && Path::new(address).exists() {
+        PathBuf::from(address)
+    } else {
+        return None;
+    };
+
+    // Tidy `./docs/../index.html` into a real location; a path that doesn't exist yet
+    // keeps its dots
+    Some(path.canonicalize().unwrap_or(path))
+}
+
+/// The public suffix a dotted `host` ends in, e.g. `co.uk` for `bbc.co.uk` or `github.io`
+/// for `rust-lang.github.io`, or a private network name such as `internal`. `None` when the last label
+/// is no known top-level domain, as in `notes.txt`.
+pub fn known_suffix(host: &str) -> Option<String> {
+    let host = host.trim_end_matches('.').to_ascii_lowercase();
+    let (_, last_label) = host.rsplit_once('.')?;
+    if PRIVATE_SUFFIXES.contains(&last_label) {
+        return Some(last_label.to_string());
+    }
+
+    PUBLIC_SUFFIXES
+        .suffix(host.as_bytes())
+        .filter(|suffix| suffix.is_known())
+        .map(|suffix| String::from_utf8_lossy(suffix.as_bytes()).into_owned())
+}
+
+/// Whether `host`, or the parent a `*.` pattern names, is itself a public suffix such as
+/// `com` or `co.uk`, under which anyone can register a domain.
+pub fn is_public_suffix(host: &str) -> bool {
+    let host = host.trim();
+    let host = host
+        .strip_prefix("*.")
+        .unwrap_or(host)
+        .trim_end_matches('.')
+        .to_ascii_lowercase();
+    PUBLIC_SUFFIXES
+        .suffix(host.as_bytes())
+        .is_some_and(|suffix| suffix.is_known() && suffix.as_bytes() == host.as_bytes())
+}
+
+/// Fails for a URL whose scheme is neither built in nor listed in `classify.schemes`,
+/// which usually means a typo such as `htps://` that no browser could open.
+pub fn check_scheme(url: &str, options: &ClassifyOptions) -> Result<()> {
+    let Ok(parsed) = Url::parse(url) else {
+        return Ok(());
+    };
+    if options.allows_scheme(parsed.scheme()) {
+        return Ok(());
+    }
+
+    let allowed: Vec<&str> = BUILTIN_SCHEMES
+        .iter()
+        .copied()
+        .chain(options.schemes.iter().map(String::as_str))
+        .collect();
+    anyhow::bail!(
+        "'{}' is not a scheme otot opens (allowed: {}). If {} is right, allow it with `otot config set -k classify.schemes -n {}`",
+        parsed.scheme(),
+        allowed.join(", "),
+        url,
+        options
+            .schemes
+            .iter()
+            .map(String::as_str)
+            .chain([parsed.scheme()])
+            .collect::<Vec<_>>()
+            .join(",")
     )
 }
 
+/// `localhost`, its subdomains, and IP literals.
+fn is_local_host(url: &Url) -> bool {
+    match url.host() {
+        Some(Host::Ipv4(_) | Host::Ipv6(_)) => true,
+        Some(Host::Domain(domain)) => domain == "localhost" || domain.ends_with(".localhost"),
+        _ => false,
+    }
+}
+
+/// Rewrites an address into the form every other function understands with the default
+/// options: patterns typed with custom separators are joined with `/`, a known host that
+/// would otherwise read as a pattern gets its scheme spelled out, and a local path becomes
+/// a `file://` URL. Other URLs are returned unchanged.
+pub fn canonical_address<'a>(address: &'a str, options: &ClassifyOptions) -> Cow<'a, str> {
+    match classify_input_with(address, options) {
+        InputType::FullUrl(url) => match classify_input(address) {
+            InputType::FullUrl(default) if default == url => Cow::Borrowed(address),
+            _ => Cow::Owned(url.to_string()),
+        },
+        InputType::LocalPath(path) => match Url::from_file_path(&path) {
+            Ok(url) => Cow::Owned(url.to_string()),
+            Err(()) => Cow::Borrowed(address),
+        },
+        InputType::FuzzyPattern(_) => {
+            let segments: Vec<&str> = options
+                .separators
+                .split(clean_pasted_address(address))
+                .collect();
+            Cow::Owned(segments.join("/"))
+        }
+    }
+}
+
+/// Strips what tends to come along when a URL is pasted from chat, email or Markdown:
+/// surrounding `(...)` or `<...>`, `[text](url)` link syntax, and unbalanced trailing
+/// punctuation.
+pub fn clean_pasted_address(input: &str) -> &str {
+    let mut address = strip_wrapping_parentheses(trim_unbalanced_trailing(input.trim()));
+
+    if address.starts_with('[')
+        && let Some(start) = address.find("](")
+        && address.ends_with(')')
+    {
+        address = &address[start + 2..address.len() - 1];
+    }
+
+    if let Some(inner) = address.strip_prefix('<').and_then(|a| a.strip_suffix('>')) {
+        address = inner;
+    }
+
+    trim_unbalanced_trailing(address.trim())
+}
+
+/// Drops a `(` at the start together with the `)` at the end when they are a pair, so
+/// `(example.com/a)` loses them but `(a)/(b)` and a lone `(example.com` keep them.
+fn strip_wrapping_parentheses(s: &str) -> &str {
+    let Some(inner) = s.strip_prefix('(').and_then(|s| s.strip_suffix(')')) else {
+        return s;
+    };
+
+    let mut depth = 0usize;
+    for c in inner.chars() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth == 0 => return s,
+            ')' => depth -= 1,
+            _ => {}
+        }
+    }
+    if depth == 0 { inner } else { s }
+}
+
+/// Drops trailing sentence punctuation, and closing brackets that have no opening partner,
+/// so `wiki/Rust_(language)` survives but `(see example.com/a).` loses its `).`.
+fn trim_unbalanced_trailing(mut s: &str) -> &str {
+    loop {
+        let Some(last) = s.chars().last() else {
+            return s;
+        };
+
+        let unbalanced = match last {
+            '.' | ',' | ';' | ':' | '!' | '?' | '"' | '\'' => true,
+            ')' => s.matches('(').count() < s.matches(')').count(),
+            ']' => s.matches('[').count() < s.matches(']').count(),
+            '}' => s.matches('{').count() < s.matches('}').count(),
+            '>' => s.matches('<').count() < s.matches('>').count(),
+            _ => false,
+        };
+
+        if !unbalanced {
+            return s;
+        }
+        s = &s[..s.len() - last.len_utf8()];
+    }
+}
+
+/// Canonical form used for both stored segments and typed patterns: percent-decoded, NFC,
+/// with full Unicode case folding, so `caf%C3%A9`, `cafe\u{301}` and `CAFÉ` all compare equal.
+pub fn normalize_segment(segment: &str) -> String {
+    let decoded = decode_segment(segment);
+    let composed: String = decoded.nfc().collect();
+    caseless::default_case_fold_str(&composed).nfc().collect()
+}
+
+/// `url` in the same canonical form as `normalize_segment`, for matching typed text against
+/// whole URLs: `https://example.com/Caf%C3%A9` and `https://example.com/café` compare equal.
+pub fn normalize_url(url: &str) -> String {
+    normalize_segment(url)
+}
+
+/// Returns true when `host` is `pattern` or one of its subdomains. A leading `*.` in the
+/// pattern matches subdomains only.
+pub fn host_matches(host: &str, pattern: &str) -> bool {
+    let host = host.trim_end_matches('.').to_lowercase();
+    let pattern = pattern.trim().trim_end_matches('.').to_lowercase();
+
+    match pattern.strip_prefix("*.") {
+        Some(parent) => host.ends_with(&format!(".{}", parent)),
+        None => host == pattern || host.ends_with(&format!(".{}", pattern)),
+    }
+}
+
+/// Returns true when `url` parses and its host matches any of `patterns`.
+pub fn url_host_matches_any(url: &str, patterns: &[String]) -> bool {
+    Url::parse(url)
+        .ok()
+        .and_then(|u| u.host_str().map(String::from))
+        .is_some_and(|host| patterns.iter().any(|p| host_matches(&host, p)))
+}
+
+/// Finds every http(s) URL embedded in free-form text, such as the output of another command.
+pub fn extract_urls(text: &str) -> Vec<Url> {
+    let mut urls = Vec::new();
+    let mut rest = text;
+
+    while let Some(start) = find_url_start(rest) {
+        let candidate = &rest[start..];
+        let end = candidate
+            .find(|c: char| c.is_whitespace() || matches!(c, '"' | '\'' | '<' | '>' | '`'))
+            .unwrap_or(candidate.len());
+        let raw = trim_unbalanced_trailing(&candidate[..end]);
+
+        if let Ok(url) = Url::parse(raw)
+            && url.host_str().is_some()
+        {
+            urls.push(url);
+        }
+
+        rest = &candidate[end.max(1)..];
+    }
+
+    urls
+}
+
+fn find_url_start(text: &str) -> Option<usize> {
+    match (text.find("https://"), text.find("http://")) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Decodes `%XX` escapes. Malformed escapes are kept literally, and a segment that doesn't
+/// decode to valid UTF-8 is returned unchanged.
+fn decode_segment(segment: &str) -> Cow<'_, str> {
+    percent_decode_str(segment)
+        .decode_utf8()
+        .unwrap_or(Cow::Borrowed(segment))
+}
+
+#[cfg(test)]
+mod clean_pasted_address_tests {
+    use super::*;
+
+    #[test]
+    fn strips_trailing_sentence_punctuation() {
+        assert_eq!(
+            clean_pasted_address("https://example.com/docs."),
+            "https://example.com/docs"
+        );
+        assert_eq!(
+            clean_pasted_address("https://example.com/a?b=c,"),
+            "https://example.com/a?b=c"
+        );
+    }
+
+    #[test]
+    fn keeps_balanced_parentheses() {
+        assert_eq!(
+            clean_pasted_address("https://en.wikipedia.org/wiki/Rust_(programming_language)"),
+            "https://en.wikipedia.org/wiki/Rust_(programming_language)"
+        );
+    }
+
+    #[test]
+    fn strips_unbalanced_parenthesis_after_balanced_one() {
+        assert_eq!(
+            clean_pasted_address("https://en.wikipedia.org/wiki/Rust_(programming_language))."),
+            "https://en.wikipedia.org/wiki/Rust_(programming_language)"
+        );
+    }
+
+    #[test]
+    fn strips_surrounding_parentheses() {
+        assert_eq!(
+            clean_pasted_address("(https://example.com/a)"),
+            "https://example.com/a"
+        );
+        assert_eq!(
+            clean_pasted_address("(https://en.wikipedia.org/wiki/Rust_(programming_language))."),
+            "https://en.wikipedia.org/wiki/Rust_(programming_language)"
+        );
+    }
+
+    #[test]
+    fn keeps_parentheses_that_are_not_a_wrapping_pair() {
+        assert_eq!(
+            clean_pasted_address("(https://example.com/a"),
+            "(https://example.com/a"
+        );
+        assert_eq!(clean_pasted_address("(draft)/(final)"), "(draft)/(final)");
+        assert_eq!(clean_pasted_address("(draft) notes"), "(draft) notes");
+    }
+
+    #[test]
+    fn strips_angle_brackets() {
+        assert_eq!(
+            clean_pasted_address(" <https://example.com/a>, "),
+            "https://example.com/a"
+        );
+    }
+
+    #[test]
+    fn extracts_markdown_link_target() {
+        assert_eq!(
+            clean_pasted_address("[the docs](https://example.com/docs)."),
+            "https://example.com/docs"
+        );
+        assert_eq!(
+            clean_pasted_address(
+                "[Rust](https://en.wikipedia.org/wiki/Rust_(programming_language))"
+            ),
+            "https://en.wikipedia.org/wiki/Rust_(programming_language)"
+        );
+    }
+
+    #[test]
+    fn leaves_patterns_alone() {
+        assert_eq!(clean_pasted_address("gh/rust"), "gh/rust");
+    }
+
+    #[test]
+    fn classify_input_cleans_pasted_urls() {
+        match classify_input("<https://example.com/docs>.") {
+            InputType::FullUrl(url) => assert_eq!(url.as_str(), "https://example.com/docs"),
+            _ => panic!("Expected FullUrl variant"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod normalize_segment_tests {
+    use super::*;
+
+    #[test]
+    fn composes_combining_characters() {
+        assert_eq!(normalize_segment("cafe\u{301}"), "caf\u{e9}");
+    }
+
+    #[test]
+    fn decomposed_and_precomposed_inputs_match() {
+        assert_eq!(
+            normalize_segment("Cafe\u{301}"),
+            normalize_segment("CAF\u{c9}")
+        );
+    }
+
+    #[test]
+    fn folds_cyrillic_case() {
+        assert_eq!(normalize_segment("Новости"), "новости");
+    }
+
+    #[test]
+    fn uses_full_case_folding() {
+        assert_eq!(normalize_segment("Straße"), normalize_segment("STRASSE"));
+    }
+
+    #[test]
+    fn decodes_percent_encoding() {
+        assert_eq!(normalize_segment("caf%C3%A9"), "caf\u{e9}");
+        assert_eq!(normalize_segment("hello%20world"), "hello world");
+    }
+
+    #[test]
+    fn keeps_malformed_escapes() {
+        assert_eq!(normalize_segment("100%zz"), "100%zz");
+        assert_eq!(normalize_segment("50%"), "50%");
+    }
+
+    #[test]
+    fn keeps_segments_that_decode_to_invalid_utf8() {
+        assert_eq!(normalize_segment("%FF%FE"), "%ff%fe");
+    }
+
+    #[test]
+    fn classify_input_normalizes_pattern_segments() {
+        assert_eq!(
+            classify_input("Cafe\u{301}/Меню"),
+            InputType::FuzzyPattern(vec!["caf\u{e9}".to_string(), "меню".to_string()])
+        );
+    }
+}
+
+#[cfg(test)]
+mod host_matches_tests {
+    use super::*;
+
+    #[test]
+    fn matches_exact_host() {
+        assert!(host_matches("reddit.com", "reddit.com"));
+    }
+
+    #[test]
+    fn plain_pattern_matches_subdomains() {
+        assert!(host_matches("old.reddit.com", "reddit.com"));
+    }
+
+    #[test]
+    fn plain_pattern_does_not_match_suffix_of_label() {
+        assert!(!host_matches("notreddit.com", "reddit.com"));
+    }
+
+    #[test]
+    fn wildcard_pattern_matches_only_subdomains() {
+        assert!(host_matches("www.youtube.com", "*.youtube.com"));
+        assert!(!host_matches("youtube.com", "*.youtube.com"));
+    }
+
+    #[test]
+    fn matching_ignores_case() {
+        assert!(host_matches("News.YCombinator.com", "news.ycombinator.COM"));
+    }
+
+    #[test]
+    fn url_host_matches_any_checks_parsed_host() {
+        let patterns = vec!["reddit.com".to_string(), "*.youtube.com".to_string()];
+        assert!(url_host_matches_any(
+            "https://www.reddit.com/r/rust",
+            &patterns
+        ));
+        assert!(!url_host_matches_any(
+            "https://github.com/reddit.com",
+            &patterns
+        ));
+        assert!(!url_host_matches_any("not a url", &patterns));
+    }
+}
+
+#[cfg(test)]
+mod extract_urls_tests {
+    use super::*;
+
+    #[test]
+    fn extracts_url_from_sentence() {
+        let urls =
+            extract_urls("Created pull request https://github.com/owner/repo/pull/42 for you");
+        assert_eq!(urls.len(), 1);
+        assert_eq!(urls[0].as_str(), "https://github.com/owner/repo/pull/42");
+    }
+
+    #[test]
+    fn extracts_multiple_urls_in_order() {
+        let urls =
+            extract_urls("build: http://ci.example.com/builds/7\nlogs: https://logs.example.com/7");
+        let urls: Vec<&str> = urls.iter().map(|u| u.as_str()).collect();
+        assert_eq!(
+            urls,
+            vec![
+                "http://ci.example.com/builds/7",
+                "https://logs.example.com/7"
+            ]
+        );
+    }
+
+    #[test]
+    fn strips_trailing_sentence_punctuation() {
+        let urls = extract_urls("See https://example.com/docs.");
+        assert_eq!(urls[0].as_str(), "https://example.com/docs");
+    }
+
+    #[test]
+    fn keeps_balanced_closing_parenthesis() {
+        let urls = extract_urls("(see https://en.wikipedia.org/wiki/Rust_(programming_language))");
+        assert_eq!(
+            urls[0].as_str(),
+            "https://en.wikipedia.org/wiki/Rust_(programming_language)"
+        );
+    }
+
+    #[test]
+    fn stops_at_quotes_and_angle_brackets() {
+        let urls = extract_urls("url=\"https://example.com/a\" <https://example.com/b>");
+        let urls: Vec<&str> = urls.iter().map(|u| u.as_str()).collect();
+        assert_eq!(urls, vec!["https://example.com/a", "https://example.com/b"]);
+    }
+
+    #[test]
+    fn ignores_text_without_urls() {
+        assert!(extract_urls("Apply complete! Resources: 3 added.").is_empty());
+    }
+
+    #[test]
+    fn ignores_bare_scheme() {
+        assert!(extract_urls("protocol is https:// only").is_empty());
+    }
+}
+
 #[cfg(test)]
 mod classify_input_tests {
     use super::*;
@@ -165,7 +822,7 @@ mod classify_input_tests {
             _ => panic!("Expected FullUrl variant"),
         }
     }
-    // Rule 3: Localhost with Port (Known to fail with current implementation)
+    // Rule 3: Localhost and IP addresses, with or without a port
     #[test]
     fn localhost_with_port_should_be_full_url() {
         let result = classify_input("localhost:8080");
@@ -176,9 +833,7 @@ mod classify_input_tests {
                 assert_eq!(url.host_str(), Some("localhost"));
                 assert_eq!(url.port(), Some(8080));
             }
-            _ => {
-                panic!("Expected FullUrl variant, but current implementation returns FuzzyPattern")
-            }
+            _ => panic!("Expected FullUrl variant"),
         }
     }
     #[test]
@@ -192,9 +847,51 @@ mod classify_input_tests {
                 assert_eq!(url.port(), Some(3000));
                 assert_eq!(url.path(), "/api");
             }
-            _ => {
-                panic!("Expected FullUrl variant, but current implementation returns FuzzyPattern")
+            _ => panic!("Expected FullUrl variant"),
+        }
+    }
+    #[test]
+    fn bare_localhost_uses_http() {
+        let result = classify_input("localhost/admin");
+
+        match result {
+            InputType::FullUrl(url) => {
+                assert_eq!(url.as_str(), "http://localhost/admin");
             }
+            _ => panic!("Expected FullUrl variant"),
+        }
+        assert!(matches!(
+            classify_input("app.localhost"),
+            InputType::FullUrl(url) if url.scheme() == "http"
+        ));
+    }
+    #[test]
+    fn ipv6_literals_use_http() {
+        let url_of = |address| match classify_input(address) {
+            InputType::FullUrl(url) => url.to_string(),
+            other => panic!("Expected FullUrl for {}, got {:?}", address, other),
+        };
+
+        assert_eq!(url_of("[::1]:8080/api"), "http://[::1]:8080/api");
+        assert_eq!(url_of("[fe80::1]/status"), "http://[fe80::1]/status");
+        assert_eq!(url_of("::1"), "http://[::1]/");
+    }
+    #[test]
+    fn ip_address_without_port_uses_http() {
+        let result = classify_input("10.0.0.1");
+
+        match result {
+            InputType::FullUrl(url) => assert_eq!(url.as_str(), "http://10.0.0.1/"),
+            _ => panic!("Expected FullUrl variant"),
+        }
+    }
+    #[test]
+    fn bare_host_with_port_is_full_url() {
+        let result = classify_input("devbox:9000/status");
+
+        match result {
+            InputType::FullUrl(url) => assert_eq!(url.as_str(), "http://devbox:9000/status"),
+            _ => panic!("Expected FullUrl variant"),
         }
     }
     // Rule 4: Fuzzy Patterns
@@ -264,4 +961,339 @@ mod classify_input_tests {
             _ => panic!("Expected FuzzyPattern variant"),
         }
     }
+    #[test]
+    fn fuzzy_pattern_splits_on_whitespace() {
+        let result = classify_input("github rust  issues");
+
+        match result {
+            InputType::FuzzyPattern(segments) => {
+                assert_eq!(segments, vec!["github", "rust", "issues"]);
+            }
+            _ => panic!("Expected FuzzyPattern variant"),
+        }
+    }
+    #[test]
+    fn fuzzy_pattern_mixes_whitespace_and_slashes() {
+        let result = classify_input(" github.com rust/issues ");
+
+        match result {
+            InputType::FuzzyPattern(segments) => {
+                assert_eq!(segments, vec!["github.com", "rust", "issues"]);
+            }
+            _ => panic!("Expected FuzzyPattern variant"),
+        }
+    }
+    #[test]
+    fn explicit_scheme_with_space_stays_a_url() {
+        let result = classify_input("https://example.com/a b");
+
+        match result {
+            InputType::FullUrl(url) => assert_eq!(url.path(), "/a%20b"),
+            _ => panic!("Expected FullUrl variant"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod separators_tests {
+    use super::*;
+
+    fn pattern(address: &str, spec: &str) -> Vec<String> {
+        match classify_input_with(address, &Separators::parse(spec).unwrap().into()) {
+            InputType::FuzzyPattern(segments) => segments,
+            other => panic!("Expected FuzzyPattern, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn dots_split_patterns_when_configured() {
+        assert_eq!(
+            pattern("github.rust.issues", "/ ."),
+            ["github", "rust", "issues"]
+        );
+        assert_eq!(
+            pattern("github.rust issues", "/ ."),
+            ["github", "rust", "issues"]
+        );
+    }
+
+    #[test]
+    fn dotted_address_with_path_stays_a_url() {
+        let separators = Separators::parse("/ .").unwrap().into();
+
+        assert!(matches!(
+            classify_input_with("github.com/rust", &separators),
+            InputType::FullUrl(_)
+        ));
+        assert!(matches!(
+            classify_input_with("https://github.com", &separators),
+            InputType::FullUrl(_)
+        ));
+    }
+
+    #[test]
+    fn commas_split_even_dotted_addresses() {
+        assert_eq!(pattern("github.com,rust", "/,"), ["github.com", "rust"]);
+    }
+
+    #[test]
+    fn slash_and_whitespace_always_split() {
+        assert_eq!(
+            pattern("github rust/issues", ","),
+            ["github", "rust", "issues"]
+        );
+    }
+
+    #[test]
+    fn rejects_separators_that_appear_in_addresses() {
+        assert!(Separators::parse("/a").is_err());
+        assert!(Separators::parse(":").is_err());
+        assert!(Separators::parse("%").is_err());
+    }
+
+    #[test]
+    fn canonical_address_joins_segments_with_slashes() {
+        let separators = Separators::parse("/ .").unwrap().into();
+
+        assert_eq!(
+            canonical_address("github.Rust.issues", &separators),
+            "github/Rust/issues"
+        );
+        assert_eq!(
+            canonical_address("https://github.com/a", &separators),
+            "https://github.com/a"
+        );
+        assert_eq!(
+            canonical_address("github rust", &ClassifyOptions::default()),
+            "github/rust"
+        );
+    }
+}
+
+#[cfg(test)]
+mod known_hosts_tests {
+    use super::*;
+
+    fn options() -> ClassifyOptions {
+        ClassifyOptions {
+            known_hosts: vec!["wiki".to_string(), "jenkins".to_string()],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn known_hosts_are_urls() {
+        match classify_input_with("wiki/Onboarding", &options()) {
+            InputType::FullUrl(url) => assert_eq!(url.as_str(), "https://wiki/Onboarding"),
+            other => panic!("Expected FullUrl, got {:?}", other),
+        }
+        assert!(matches!(
+            classify_input_with("JENKINS", &options()),
+            InputType::FullUrl(_)
+        ));
+    }
+
+    #[test]
+    fn other_dotless_words_stay_patterns() {
+        assert!(matches!(
+            classify_input_with("wikipedia/rust", &options()),
+            InputType::FuzzyPattern(_)
+        ));
+        assert!(matches!(
+            classify_input("wiki/Onboarding"),
+            InputType::FuzzyPattern(_)
+        ));
+    }
+
+    #[test]
+    fn canonical_address_spells_out_known_hosts() {
+        assert_eq!(
+            canonical_address("wiki/Onboarding", &options()),
+            "https://wiki/Onboarding"
+        );
+        assert_eq!(
+            canonical_address("github.com/rust", &options()),
+            "github.com/rust"
+        );
+    }
+}
+
+#[cfg(test)]
+mod public_suffix_tests {
+    use super::*;
+
+    #[test]
+    fn hosts_ending_in_a_public_suffix_are_urls() {
+        for address in [
+            "crates.io/crates/url",
+            "bbc.co.uk",
+            "rust-lang.github.io/book",
+        ] {
+            assert!(
+                matches!(classify_input(address), InputType::FullUrl(_)),
+                "{} should be a URL",
+                address
+            );
+        }
+        assert_eq!(known_suffix("news.bbc.co.uk").as_deref(), Some("co.uk"));
+        assert_eq!(known_suffix("wiki.internal").as_deref(), Some("internal"));
+    }
+
+    #[test]
+    fn public_suffixes_are_recognized_as_patterns() {
+        for suffix in ["com", "*.com", "co.uk", "github.io", "COM."] {
+            assert!(is_public_suffix(suffix), "{}", suffix);
+        }
+        for host in [
+            "example.com",
+            "*.example.com",
+            "internal",
+            "*.internal",
+            "jenkins",
+        ] {
+            assert!(!is_public_suffix(host), "{}", host);
+        }
+    }
+
+    #[test]
+    fn international_hosts_are_stored_as_punycode() {
+        for (address, host) in [
+            ("münchen.de", "xn--mnchen-3ya.de"),
+            ("xn--mnchen-3ya.de/rathaus", "xn--mnchen-3ya.de"),
+            ("пример.рф", "xn--e1afmkfd.xn--p1ai"),
+        ] {
+            match classify_input(address) {
+                InputType::FullUrl(url) => assert_eq!(url.host_str(), Some(host)),
+                other => panic!("{} should be a URL, got {:?}", address, other),
+            }
+        }
+    }
+
+    #[test]
+    fn dotted_words_without_a_suffix_are_patterns() {
+        for address in ["notes.txt", "v1.2/changelog", "config.toml", "report.pdf"] {
+            assert!(
+                matches!(classify_input(address), InputType::FuzzyPattern(_)),
+                "{} should be a pattern",
+                address
+            );
+        }
+        assert_eq!(known_suffix("localhost"), None);
+    }
+
+    #[test]
+    fn known_hosts_cover_private_domains() {
+        let options = ClassifyOptions {
+            known_hosts: vec!["acme".to_string()],
+            ..Default::default()
+        };
+        assert!(matches!(
+            classify_input_with("build.acme/job/42", &options),
+            InputType::FullUrl(_)
+        ));
+    }
+
+    #[test]
+    fn host_schemes_override_the_inferred_scheme() {
+        let options = ClassifyOptions {
+            host_schemes: BTreeMap::from([
+                ("*.internal".to_string(), "http".to_string()),
+                ("secure.internal".to_string(), "https".to_string()),
+                ("jenkins".to_string(), "http".to_string()),
+                ("localhost".to_string(), "https".to_string()),
+            ]),
+            ..Default::default()
+        };
+        let scheme = |address| match classify_input_with(address, &options) {
+            InputType::FullUrl(url) => url.scheme().to_string(),
+            other => panic!("{} should be a URL, got {:?}", address, other),
+        };
+
+        assert_eq!(scheme("wiki.internal/page"), "http");
+        assert_eq!(scheme("secure.internal"), "https");
+        assert_eq!(scheme("jenkins/job/42"), "http");
+        assert_eq!(scheme("localhost:3000"), "https");
+        assert_eq!(scheme("github.com"), "https");
+        assert_eq!(
+            canonical_address("wiki.internal/page", &options),
+            "http://wiki.internal/page"
+        );
+        assert_eq!(canonical_address("github.com", &options), "github.com");
+    }
+}
+
+#[cfg(test)]
+mod local_path_tests {
+    use super::*;
+
+    #[test]
+    fn relative_and_home_paths_become_absolute() {
+        let cwd = std::env::current_dir().unwrap();
+        assert_eq!(
+            classify_input("./docs/index.html"),
+            InputType::LocalPath(cwd.join("docs/index.html"))
+        );
+        assert_eq!(
+            classify_input("~/report.pdf"),
+            InputType::LocalPath(dirs::home_dir().unwrap().join("report.pdf"))
+        );
+    }
+
+    #[test]
+    fn absolute_paths_must_exist() {
+        let temp_dir = assert_fs::TempDir::new().unwrap();
+        let report = temp_dir.path().join("report.pdf");
+        std::fs::write(&report, "").unwrap();
+        let report = report.canonicalize().unwrap();
+
+        assert_eq!(
+            classify_input(report.to_str().unwrap()),
+            InputType::LocalPath(report.clone())
+        );
+        assert!(matches!(
+            classify_input("/rust/issues"),
+            InputType::FuzzyPattern(_)
+        ));
+        assert_eq!(
+            canonical_address(report.to_str().unwrap(), &ClassifyOptions::default()),
+            Url::from_file_path(&report).unwrap().as_str()
+        );
+    }
+}
+
+#[cfg(test)]
+mod scheme_tests {
+    use super::*;
+
+    fn with_slack() -> ClassifyOptions {
+        ClassifyOptions {
+            schemes: vec!["slack".to_string()],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn mailto_and_allowed_schemes_are_urls() {
+        match classify_input("mailto:team@example.com") {
+            InputType::FullUrl(url) => assert_eq!(url.as_str(), "mailto:team@example.com"),
+            other => panic!("Expected FullUrl, got {:?}", other),
+        }
+        assert!(check_scheme("slack://channel?id=C123", &with_slack()).is_ok());
+        assert!(matches!(
+            classify_input_with("localhost:3000", &with_slack()),
+            InputType::FullUrl(url) if url.scheme() == "http"
+        ));
+    }
+
+    #[test]
+    fn unknown_schemes_are_refused_with_the_fix() {
+        let err = check_scheme("htps://github.com/", &with_slack())
+            .unwrap_err()
+            .to_string();
+
+        assert!(err.contains("'htps' is not a scheme otot opens"));
+        assert!(err.contains("http, https, file, mailto, ssh, slack"));
+        assert!(err.contains("-n slack,htps"));
+        assert!(check_scheme("slack://channel", &ClassifyOptions::default()).is_err());
+    }
 }
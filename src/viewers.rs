@@ -0,0 +1,145 @@
+use anyhow::Result;
+use percent_encoding::{NON_ALPHANUMERIC, utf8_percent_encode};
+use std::collections::BTreeMap;
+
+/// Viewers available without any configuration. `{url}` is replaced by the URL as is,
+/// `{url_encoded}` by the percent-encoded URL and `{arg}` by whatever follows the colon in
+/// `--as name:arg`.
+const BUILTIN_VIEWERS: &[(&str, &str)] = &[
+    ("cache", "https://web.archive.org/web/2/{url}"),
+    ("reader", "https://r.jina.ai/{url}"),
+    (
+        "translate",
+        "https://translate.google.com/translate?sl=auto&tl={arg}&u={url_encoded}",
+    ),
+];
+
+/// Rewrites `url` to open it through the viewer named by `spec`, e.g. `cache` or
+/// `translate:de`. Viewers from the config take precedence over built-in ones.
+pub fn view_url(spec: &str, url: &str, custom: &BTreeMap<String, String>) -> Result<String> {
+    let (name, arg) = match spec.split_once(':') {
+        Some((name, arg)) => (name, Some(arg).filter(|a| !a.is_empty())),
+        None => (spec, None),
+    };
+
+    let template = custom
+        .get(name)
+        .map(String::as_str)
+        .or_else(|| {
+            BUILTIN_VIEWERS
+                .iter()
+                .find(|(builtin, _)| *builtin == name)
+                .map(|(_, template)| *template)
+        })
+        .ok_or_else(|| {
+            let mut names: Vec<&str> = BUILTIN_VIEWERS.iter().map(|(name, _)| *name).collect();
+            names.extend(custom.keys().map(String::as_str));
+            names.sort_unstable();
+            names.dedup();
+            anyhow::anyhow!(
+                "Unknown viewer '{}'. Available viewers: {}",
+                name,
+                names.join(", ")
+            )
+        })?;
+
+    let takes_arg = template.contains("{arg}");
+    let arg = match (takes_arg, arg) {
+        (true, Some(arg)) => utf8_percent_encode(arg, NON_ALPHANUMERIC).to_string(),
+        (true, None) => anyhow::bail!(
+            "Viewer '{}' needs an argument, e.g. `--as {}:de`",
+            name,
+            name
+        ),
+        (false, Some(_)) => anyhow::bail!("Viewer '{}' does not take an argument", name),
+        (false, None) => String::new(),
+    };
+
+    Ok(fill_url_placeholders(template, url).replace("{arg}", &arg))
+}
+
+/// `template` with `{url}` replaced by `url` as is and `{url_encoded}` by it percent-encoded.
+pub fn fill_url_placeholders(template: &str, url: &str) -> String {
+    template
+        .replace(
+            "{url_encoded}",
+            &utf8_percent_encode(url, NON_ALPHANUMERIC).to_string(),
+        )
+        .replace("{url}", url)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const URL: &str = "https://example.com/a?b=c";
+
+    #[test]
+    fn builtin_cache_and_reader_prefix_the_url() {
+        let none = BTreeMap::new();
+
+        assert_eq!(
+            view_url("cache", URL, &none).unwrap(),
+            "https://web.archive.org/web/2/https://example.com/a?b=c"
+        );
+        assert_eq!(
+            view_url("reader", URL, &none).unwrap(),
+            "https://r.jina.ai/https://example.com/a?b=c"
+        );
+    }
+
+    #[test]
+    fn translate_takes_target_language_and_encodes_url() {
+        let viewed = view_url("translate:de", URL, &BTreeMap::new()).unwrap();
+
+        assert_eq!(
+            viewed,
+            "https://translate.google.com/translate?sl=auto&tl=de&u=https%3A%2F%2Fexample%2Ecom%2Fa%3Fb%3Dc"
+        );
+    }
+
+    #[test]
+    fn argument_mismatches_are_errors() {
+        let none = BTreeMap::new();
+
+        assert!(
+            view_url("translate", URL, &none)
+                .unwrap_err()
+                .to_string()
+                .contains("needs an argument")
+        );
+        assert!(
+            view_url("cache:de", URL, &none)
+                .unwrap_err()
+                .to_string()
+                .contains("does not take an argument")
+        );
+    }
+
+    #[test]
+    fn configured_viewers_override_builtins() {
+        let custom = BTreeMap::from([
+            ("cache".to_string(), "https://archive.ph/{url}".to_string()),
+            (
+                "lite".to_string(),
+                "https://lite.example/?u={url_encoded}".to_string(),
+            ),
+        ]);
+
+        assert_eq!(
+            view_url("cache", URL, &custom).unwrap(),
+            "https://archive.ph/https://example.com/a?b=c"
+        );
+        assert!(view_url("lite", URL, &custom).is_ok());
+    }
+
+    #[test]
+    fn unknown_viewer_lists_available_ones() {
+        let err = view_url("nope", URL, &BTreeMap::new()).unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "Unknown viewer 'nope'. Available viewers: cache, reader, translate"
+        );
+    }
+}
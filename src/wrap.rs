@@ -0,0 +1,201 @@
+use anyhow::{Context, Result};
+use log::debug;
+use std::ffi::{OsStr, OsString};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::process::{Command, ExitStatus, Stdio};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::thread;
+
+use crate::url_classify::extract_urls;
+
+pub struct WrappedOutput {
+    pub status: ExitStatus,
+    /// URLs printed by the command on stdout or stderr, deduplicated, in the order they
+    /// first appeared.
+    pub urls: Vec<String>,
+}
+
+impl WrappedOutput {
+    /// The status a shell would report for the command: its exit code, or 128 plus the
+    /// signal that killed it.
+    pub fn exit_code(&self) -> i32 {
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::ExitStatusExt;
+            if let Some(signal) = self.status.signal() {
+                return 128 + signal;
+            }
+        }
+        self.status.code().unwrap_or(1)
+    }
+}
+
+/// Runs `command`, passing its stdout and stderr through unchanged while collecting any
+/// URLs it prints.
+pub fn run_wrapped(command: &[OsString]) -> Result<WrappedOutput> {
+    let (program, args) = command.split_first().context("No command given to wrap")?;
+
+    // The wrapped command shares our terminal, so Ctrl-C reaches it directly. Registering
+    // a handler keeps otot alive long enough to record what the command printed.
+    let interrupted = Arc::new(AtomicBool::new(false));
+    let sig_id = signal_hook::flag::register(signal_hook::consts::SIGINT, interrupted)
+        .context("Failed to register interrupt handler")?;
+
+    let result = spawn_and_collect(program, args);
+    signal_hook::low_level::unregister(sig_id);
+    result
+}
+
+fn spawn_and_collect(program: &OsStr, args: &[OsString]) -> Result<WrappedOutput> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::inherit())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Failed to run '{}'", program.to_string_lossy()))?;
+
+    let stdout = child.stdout.take().context("Failed to capture stdout")?;
+    let stderr = child.stderr.take().context("Failed to capture stderr")?;
+
+    // Both readers number the lines they see from one counter, so the URLs of the two
+    // streams can be put back in the order the command printed them
+    let lines = Arc::new(AtomicU64::new(0));
+    let stdout_lines = Arc::clone(&lines);
+    let stdout_reader = thread::spawn(move || tee_lines(stdout, std::io::stdout(), &stdout_lines));
+    let stderr_reader = thread::spawn(move || tee_lines(stderr, std::io::stderr(), &lines));
+
+    let status = child.wait().context("Failed to wait for wrapped command")?;
+
+    let mut found = Vec::new();
+    for reader in [stdout_reader, stderr_reader] {
+        found.extend(
+            reader
+                .join()
+                .map_err(|_| anyhow::anyhow!("Output reader thread panicked"))??,
+        );
+    }
+    found.sort_by_key(|(line, _)| *line);
+
+    let mut urls: Vec<String> = Vec::new();
+    for (_, url) in found {
+        if !urls.contains(&url) {
+            urls.push(url);
+        }
+    }
+
+    debug!(
+        "Wrapped command exited with {}, printed {} URL(s)",
+        status,
+        urls.len()
+    );
+
+    Ok(WrappedOutput { status, urls })
+}
+
+/// Copies `source` to `sink` line by line, returning the URLs found with the number each
+/// line drew from `lines`.
+fn tee_lines(
+    source: impl Read,
+    mut sink: impl Write,
+    lines: &AtomicU64,
+) -> Result<Vec<(u64, String)>> {
+    let mut reader = BufReader::new(source);
+    let mut line = Vec::new();
+    let mut urls = Vec::new();
+
+    loop {
+        line.clear();
+        if reader.read_until(b'\n', &mut line)? == 0 {
+            break;
+        }
+        let number = lines.fetch_add(1, Ordering::SeqCst);
+        sink.write_all(&line)?;
+        sink.flush()?;
+
+        urls.extend(
+            extract_urls(&String::from_utf8_lossy(&line))
+                .into_iter()
+                .map(|url| (number, url.to_string())),
+        );
+    }
+
+    Ok(urls)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tee_lines_passes_output_through_and_collects_urls() {
+        let input = "Creating pull request...\nhttps://github.com/owner/repo/pull/1\ndone\n";
+        let mut sink = Vec::new();
+
+        let urls = tee_lines(input.as_bytes(), &mut sink, &AtomicU64::new(0)).unwrap();
+
+        assert_eq!(sink, input.as_bytes());
+        assert_eq!(
+            urls,
+            vec![(1, "https://github.com/owner/repo/pull/1".to_string())]
+        );
+    }
+
+    #[test]
+    fn empty_command_is_rejected() {
+        assert!(run_wrapped(&[]).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn run_wrapped_collects_urls_from_stdout_and_stderr() {
+        let command = [
+            "sh".into(),
+            "-c".into(),
+            "echo https://a.example.com/1; echo https://b.example.com/2 >&2; echo https://a.example.com/1; exit 3"
+                .into(),
+        ];
+
+        let output = run_wrapped(&command).unwrap();
+
+        assert_eq!(output.status.code(), Some(3));
+        assert_eq!(output.urls.len(), 2);
+        assert!(output.urls.contains(&"https://a.example.com/1".to_string()));
+        assert!(output.urls.contains(&"https://b.example.com/2".to_string()));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn urls_keep_the_order_they_were_printed_in_across_streams() {
+        let command = [
+            "sh".into(),
+            "-c".into(),
+            "echo https://a.example.com/; sleep 0.2; echo https://b.example.com/ >&2; sleep 0.2; echo https://c.example.com/; sleep 0.2; echo https://d.example.com/ >&2"
+                .into(),
+        ];
+
+        let output = run_wrapped(&command).unwrap();
+
+        assert_eq!(
+            output.urls,
+            vec![
+                "https://a.example.com/",
+                "https://b.example.com/",
+                "https://c.example.com/",
+                "https://d.example.com/"
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn commands_killed_by_a_signal_exit_with_128_plus_the_signal() {
+        let command = ["sh".into(), "-c".into(), "kill -TERM $$".into()];
+
+        let output = run_wrapped(&command).unwrap();
+
+        assert_eq!(output.status.code(), None);
+        assert_eq!(output.exit_code(), 143);
+    }
+}
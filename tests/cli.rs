@@ -12,3 +12,963 @@ fn empty_address() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+#[cfg(target_os = "linux")]
+#[test]
+fn match_porcelain_prints_only_the_url() -> Result<(), Box<dyn std::error::Error>> {
+    let home = assert_fs::TempDir::new()?;
+    let mut cmd = cargo_bin_cmd!("otot");
+
+    cmd.env("XDG_DATA_HOME", home.path())
+        .env("XDG_CONFIG_HOME", home.path())
+        .args(["match", "--porcelain", "github.com/rust-lang"]);
+    cmd.assert()
+        .success()
+        .stdout("https://github.com/rust-lang\n")
+        .stderr("");
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn match_porcelain_fails_silently_without_match() -> Result<(), Box<dyn std::error::Error>> {
+    let home = assert_fs::TempDir::new()?;
+    let mut cmd = cargo_bin_cmd!("otot");
+
+    cmd.env("XDG_DATA_HOME", home.path())
+        .env("XDG_CONFIG_HOME", home.path())
+        .args(["match", "--porcelain", "gh/rust"]);
+    cmd.assert().failure().stdout("").stderr("");
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn match_null_terminates_output_with_nul() -> Result<(), Box<dyn std::error::Error>> {
+    let home = assert_fs::TempDir::new()?;
+    let mut cmd = cargo_bin_cmd!("otot");
+
+    cmd.env("XDG_DATA_HOME", home.path())
+        .env("XDG_CONFIG_HOME", home.path())
+        .args(["match", "-0", "example.com/it's"]);
+    cmd.assert().success().stdout("https://example.com/it's\0");
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn portable_flag_file_keeps_data_next_to_binary() -> Result<(), Box<dyn std::error::Error>> {
+    let home = assert_fs::TempDir::new()?;
+    let tools = assert_fs::TempDir::new()?;
+    let binary = tools.path().join("otot");
+    std::fs::copy(cargo_bin!("otot"), &binary)?;
+    std::fs::write(tools.path().join("portable.flag"), "")?;
+
+    let mut cmd = std::process::Command::new(&binary);
+    cmd.env("XDG_DATA_HOME", home.path())
+        .env("XDG_CONFIG_HOME", home.path())
+        .args(["config", "set", "-k", "browser.preferred", "-n", "firefox"]);
+    assert_cmd::Command::from_std(cmd).assert().success();
+
+    let mut cmd = std::process::Command::new(&binary);
+    cmd.env("XDG_DATA_HOME", home.path())
+        .args(["match", "example.com"]);
+    assert_cmd::Command::from_std(cmd).assert().success();
+
+    assert!(tools.path().join("otot-data/config.toml").exists());
+    assert!(tools.path().join("otot-data/history.db").exists());
+    assert!(!home.path().join("otot").exists());
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn history_new_since_lists_newly_seen_urls() -> Result<(), Box<dyn std::error::Error>> {
+    let home = assert_fs::TempDir::new()?;
+    let otot = || {
+        let mut cmd = cargo_bin_cmd!("otot");
+        cmd.env("XDG_DATA_HOME", home.path())
+            .env("XDG_CONFIG_HOME", home.path());
+        cmd
+    };
+
+    otot()
+        .args(["wrap", "--", "echo", "https://example.com/fresh"])
+        .assert()
+        .success();
+    otot()
+        .args(["history", "--new-since", "1d"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("https://example.com/fresh"));
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn wrap_reports_a_signal_like_the_shell_does() -> Result<(), Box<dyn std::error::Error>> {
+    let home = assert_fs::TempDir::new()?;
+    let mut cmd = cargo_bin_cmd!("otot");
+    cmd.env("XDG_DATA_HOME", home.path())
+        .env("XDG_CONFIG_HOME", home.path())
+        .args(["wrap", "--", "sh", "-c", "kill -TERM $$"]);
+
+    cmd.assert().code(128 + 15);
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn packs_are_confirmed_and_plain_http_hosts_need_a_terminal()
+-> Result<(), Box<dyn std::error::Error>> {
+    let home = assert_fs::TempDir::new()?;
+    let otot = || {
+        let mut cmd = cargo_bin_cmd!("otot");
+        cmd.env("XDG_DATA_HOME", home.path())
+            .env("XDG_CONFIG_HOME", home.path());
+        cmd
+    };
+    let pack = home.path().join("team.toml");
+    std::fs::write(&pack, "[viewers]\narchive = \"https://archive.ph/{url}\"\n")?;
+    let pack = pack.to_str().unwrap();
+
+    otot()
+        .args(["pack", "install", pack])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "viewers.archive = https://archive.ph/{url}",
+        ))
+        .stderr(predicate::str::contains("--yes"));
+    otot()
+        .args(["pack", "install", pack, "--yes"])
+        .assert()
+        .success();
+
+    let http_pack = home.path().join("http.toml");
+    std::fs::write(&http_pack, "[host_schemes]\n\"*.internal\" = \"http\"\n")?;
+    otot()
+        .args(["pack", "install", http_pack.to_str().unwrap(), "--yes"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("plain http"));
+    otot()
+        .args(["config", "get", "-k", "classify.host_schemes.*.internal"])
+        .assert()
+        .stdout(predicate::str::contains("http").not());
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn private_files_stay_private_under_a_permissive_umask() -> Result<(), Box<dyn std::error::Error>> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let home = assert_fs::TempDir::new()?;
+    let export = home.path().join("state.json");
+    let status = std::process::Command::new("sh")
+        .args([
+            "-c",
+            "umask 000 && \"$0\" record https://example.com/ && \"$0\" state export \"$1\"",
+            env!("CARGO_BIN_EXE_otot"),
+        ])
+        .arg(&export)
+        .env("XDG_DATA_HOME", home.path())
+        .env("XDG_CONFIG_HOME", home.path())
+        .status()?;
+    assert!(status.success());
+
+    let mode = |path: &std::path::Path| -> std::io::Result<u32> {
+        Ok(std::fs::metadata(path)?.permissions().mode() & 0o777)
+    };
+    assert_eq!(mode(&home.path().join("otot"))?, 0o700);
+    assert_eq!(mode(&home.path().join("otot/history.db"))?, 0o600);
+    assert_eq!(mode(&export)?, 0o600);
+
+    Ok(())
+}
+
+#[test]
+fn unknown_locale_is_rejected() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = cargo_bin_cmd!("otot");
+
+    cmd.args(["--locale", "xx-YY", "stats"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Unsupported locale 'xx-YY'"));
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn pair_stores_relay_used_by_push() -> Result<(), Box<dyn std::error::Error>> {
+    let home = assert_fs::TempDir::new()?;
+    let otot = || {
+        let mut cmd = cargo_bin_cmd!("otot");
+        cmd.env("XDG_DATA_HOME", home.path())
+            .env("XDG_CONFIG_HOME", home.path());
+        cmd
+    };
+
+    otot()
+        .args(["push", "example.com"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Run `otot pair` first"));
+
+    otot()
+        .args(["pair", "--relay", "https://ntfy.example.com/otot-test"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "https://ntfy.example.com/otot-test",
+        ));
+    otot()
+        .args(["config", "get", "-k", "push.relay"])
+        .assert()
+        .success()
+        .stdout("https://ntfy.example.com/otot-test\n");
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn failed_opens_are_listed_as_misses() -> Result<(), Box<dyn std::error::Error>> {
+    let home = assert_fs::TempDir::new()?;
+    let otot = || {
+        let mut cmd = cargo_bin_cmd!("otot");
+        cmd.env("XDG_DATA_HOME", home.path())
+            .env("XDG_CONFIG_HOME", home.path());
+        cmd
+    };
+
+    otot().args(["open", "standup"]).assert().failure();
+    otot()
+        .arg("misses")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("standup"));
+    otot()
+        .args(["misses", "--clear", "standup"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Cleared 1 miss(es)"));
+    otot()
+        .arg("misses")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No misses recorded."));
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn homograph_hosts_are_refused_without_a_terminal() -> Result<(), Box<dyn std::error::Error>> {
+    let home = assert_fs::TempDir::new()?;
+
+    let mut cmd = cargo_bin_cmd!("otot");
+    cmd.env("XDG_DATA_HOME", home.path())
+        .env("XDG_CONFIG_HOME", home.path())
+        .args(["open", "\u{430}pple.com"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("mixes Cyrillic and Latin letters"))
+        .stderr(predicate::str::contains("--no-homograph-check"));
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn context_is_remembered_until_turned_off() -> Result<(), Box<dyn std::error::Error>> {
+    let home = assert_fs::TempDir::new()?;
+    let otot = || {
+        let mut cmd = cargo_bin_cmd!("otot");
+        cmd.env("XDG_DATA_HOME", home.path())
+            .env("XDG_CONFIG_HOME", home.path());
+        cmd
+    };
+
+    otot().args(["context", "use", "acme"]).assert().success();
+    otot()
+        .args(["context", "show"])
+        .assert()
+        .success()
+        .stdout("acme\n");
+    otot()
+        .args(["context", "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("* acme"));
+    otot()
+        .args(["context", "off"])
+        .assert()
+        .success()
+        .stdout("Left context acme\n");
+    otot()
+        .args(["context", "show"])
+        .assert()
+        .success()
+        .stdout("No context is active\n");
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn expanded_wildcard_is_explained_and_still_matched() -> Result<(), Box<dyn std::error::Error>> {
+    let home = assert_fs::TempDir::new()?;
+    std::fs::write(home.path().join("notes"), "")?;
+    std::fs::write(home.path().join("rust"), "")?;
+    let otot = || {
+        let mut cmd = cargo_bin_cmd!("otot");
+        cmd.env("XDG_DATA_HOME", home.path())
+            .env("XDG_CONFIG_HOME", home.path())
+            .current_dir(home.path());
+        cmd
+    };
+
+    otot()
+        .args(["record", "https://notes.example.com/rust"])
+        .assert()
+        .success();
+    otot()
+        .args(["match", "notes", "rust"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("https://notes.example.com/rust"))
+        .stderr(predicate::str::contains("expanded a wildcard"));
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn saved_history_report_runs_with_overrides() -> Result<(), Box<dyn std::error::Error>> {
+    let home = assert_fs::TempDir::new()?;
+    let otot = || {
+        let mut cmd = cargo_bin_cmd!("otot");
+        cmd.env("XDG_DATA_HOME", home.path())
+            .env("XDG_CONFIG_HOME", home.path());
+        cmd
+    };
+
+    otot()
+        .args([
+            "wrap",
+            "--",
+            "echo",
+            "https://github.com/rust-lang/rust https://example.com/docs",
+        ])
+        .assert()
+        .success();
+    otot()
+        .args([
+            "history",
+            "save",
+            "gh-week",
+            "--domain",
+            "github.com",
+            "--since",
+            "7d",
+        ])
+        .assert()
+        .success();
+    otot()
+        .args(["history", "run", "gh-week"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "https://github.com/rust-lang/rust",
+        ))
+        .stdout(predicate::str::contains("example.com").not());
+    otot()
+        .args([
+            "history",
+            "run",
+            "gh-week",
+            "--domain",
+            "example.com",
+            "--json",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "\"url\": \"https://example.com/docs\"",
+        ));
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn top_ranks_most_used_urls() -> Result<(), Box<dyn std::error::Error>> {
+    let home = assert_fs::TempDir::new()?;
+    let otot = || {
+        let mut cmd = cargo_bin_cmd!("otot");
+        cmd.env("XDG_DATA_HOME", home.path())
+            .env("XDG_CONFIG_HOME", home.path());
+        cmd
+    };
+
+    otot()
+        .args([
+            "wrap",
+            "--",
+            "echo",
+            "https://github.com/rust-lang/rust https://example.com/docs",
+        ])
+        .assert()
+        .success();
+    otot()
+        .args(["wrap", "--", "echo", "https://example.com/docs"])
+        .assert()
+        .success();
+    otot()
+        .args(["top", "1", "--by", "score"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("https://example.com/docs"))
+        .stdout(predicate::str::contains("github.com").not());
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn garbage_is_classified_and_pruned_on_its_own() -> Result<(), Box<dyn std::error::Error>> {
+    let home = assert_fs::TempDir::new()?;
+    let otot = || {
+        let mut cmd = cargo_bin_cmd!("otot");
+        cmd.env("XDG_DATA_HOME", home.path())
+            .env("XDG_CONFIG_HOME", home.path());
+        cmd
+    };
+
+    otot()
+        .args([
+            "wrap",
+            "--",
+            "echo",
+            "https://github.com/logout https://github.com/rust-lang/rust",
+        ])
+        .assert()
+        .success();
+    otot()
+        .args(["db", "classify"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("1  a logout link"));
+    otot()
+        .args(["prune", "--garbage-only"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Pruned 1 URL(s) flagged"));
+    otot()
+        .args(["history"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("rust-lang"))
+        .stdout(predicate::str::contains("logout").not());
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn rescore_previews_before_switching_the_ranking() -> Result<(), Box<dyn std::error::Error>> {
+    let home = assert_fs::TempDir::new()?;
+    let otot = || {
+        let mut cmd = cargo_bin_cmd!("otot");
+        cmd.env("XDG_DATA_HOME", home.path())
+            .env("XDG_CONFIG_HOME", home.path());
+        cmd
+    };
+
+    otot()
+        .args(["wrap", "--", "echo", "https://github.com/rust-lang/rust"])
+        .assert()
+        .success();
+    otot()
+        .args([
+            "db",
+            "rescore",
+            "--to",
+            "recency",
+            "--preview",
+            "--pattern",
+            "gh/rust",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "0 of 1 patterns would open a different URL",
+        ));
+    otot()
+        .args(["config", "get", "-k", "matching.rank"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("combined"));
+    otot()
+        .args(["db", "rescore", "--to", "recency"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("matching.rank is now recency"));
+    otot()
+        .args(["config", "get", "-k", "matching.rank"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("recency"));
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn prune_pattern_reports_deleted_count() -> Result<(), Box<dyn std::error::Error>> {
+    let home = assert_fs::TempDir::new()?;
+    let otot = || {
+        let mut cmd = cargo_bin_cmd!("otot");
+        cmd.env("XDG_DATA_HOME", home.path())
+            .env("XDG_CONFIG_HOME", home.path());
+        cmd
+    };
+
+    otot()
+        .args([
+            "wrap",
+            "--",
+            "echo",
+            "https://github.com/rust-lang/rust https://example.com/github.com",
+        ])
+        .assert()
+        .success();
+    otot()
+        .args(["prune", "--dry-run", "--pattern", r"^https://github\.com/"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "https://github.com/rust-lang/rust",
+        ))
+        .stdout(predicate::str::contains("Would prune 1 URL(s)"));
+    otot()
+        .args(["prune", "--pattern", r"^https://github\.com/"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Pruned 1 URL(s)"));
+    otot()
+        .args(["history"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("https://example.com/github.com"))
+        .stdout(predicate::str::contains("rust-lang").not());
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn auto_vacuum_compacts_after_pruning() -> Result<(), Box<dyn std::error::Error>> {
+    let home = assert_fs::TempDir::new()?;
+    let otot = || {
+        let mut cmd = cargo_bin_cmd!("otot");
+        cmd.env("XDG_DATA_HOME", home.path())
+            .env("XDG_CONFIG_HOME", home.path());
+        cmd
+    };
+
+    otot()
+        .args(["record", "https://github.com/rust-lang/rust"])
+        .assert()
+        .success();
+    otot()
+        .args(["prune", "--url", "rust-lang"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Compacted").not());
+    otot()
+        .args(["config", "set", "-k", "database.auto_vacuum", "-n", "true"])
+        .assert()
+        .success();
+    otot()
+        .args(["prune", "--url", "rust-lang"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Compacted").not());
+    otot()
+        .args(["record", "https://github.com/rust-lang/rust"])
+        .assert()
+        .success();
+    otot()
+        .args(["prune", "--url", "rust-lang"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Compacted the database from"));
+    otot()
+        .args(["db", "vacuum"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("history.db from"));
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn ambiguous_open_fails_without_terminal_when_configured() -> Result<(), Box<dyn std::error::Error>>
+{
+    let home = assert_fs::TempDir::new()?;
+    let otot = || {
+        let mut cmd = cargo_bin_cmd!("otot");
+        cmd.env("XDG_DATA_HOME", home.path())
+            .env("XDG_CONFIG_HOME", home.path());
+        cmd
+    };
+
+    otot()
+        .args([
+            "wrap",
+            "--",
+            "echo",
+            "https://github.com/rust-lang/rust https://github.com/rust-fork/rust",
+        ])
+        .assert()
+        .success();
+    otot()
+        .args(["config", "set", "-k", "matching.on_ambiguous", "-n", "fail"])
+        .assert()
+        .success();
+    otot()
+        .args(["open", "gh/rust"])
+        .assert()
+        .code(3)
+        .stderr(predicate::str::contains(
+            "https://github.com/rust-fork/rust",
+        ));
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn open_list_prints_candidates_without_opening() -> Result<(), Box<dyn std::error::Error>> {
+    let home = assert_fs::TempDir::new()?;
+    let otot = || {
+        let mut cmd = cargo_bin_cmd!("otot");
+        cmd.env("XDG_DATA_HOME", home.path())
+            .env("XDG_CONFIG_HOME", home.path());
+        cmd
+    };
+
+    otot()
+        .args([
+            "wrap",
+            "--",
+            "echo",
+            "https://github.com/rust-lang/rust https://github.com/rust-fork/rust",
+        ])
+        .assert()
+        .success();
+    otot()
+        .args(["open", "gh/rust", "--list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "https://github.com/rust-lang/rust",
+        ))
+        .stdout(predicate::str::contains(
+            "https://github.com/rust-fork/rust",
+        ));
+    otot()
+        .args(["misses"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("gh/rust").not());
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn query_prints_only_the_best_match() -> Result<(), Box<dyn std::error::Error>> {
+    let home = assert_fs::TempDir::new()?;
+    let otot = || {
+        let mut cmd = cargo_bin_cmd!("otot");
+        cmd.env("XDG_DATA_HOME", home.path())
+            .env("XDG_CONFIG_HOME", home.path());
+        cmd
+    };
+
+    otot()
+        .args([
+            "wrap",
+            "--",
+            "echo",
+            "https://github.com/rust-lang/rust https://github.com/rust-fork/rust",
+        ])
+        .assert()
+        .success();
+    otot()
+        .args(["wrap", "--", "echo", "https://github.com/rust-lang/rust"])
+        .assert()
+        .success();
+    otot()
+        .args(["query", "gh/rust"])
+        .assert()
+        .success()
+        .stdout("https://github.com/rust-lang/rust\n")
+        .stderr("");
+    otot()
+        .args(["query", "gitlab/nothing"])
+        .assert()
+        .code(1)
+        .stdout("")
+        .stderr("");
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn credentials_are_redacted_and_piped_opens_refused() -> Result<(), Box<dyn std::error::Error>> {
+    let home = assert_fs::TempDir::new()?;
+    let otot = || {
+        let mut cmd = cargo_bin_cmd!("otot");
+        cmd.env("XDG_DATA_HOME", home.path())
+            .env("XDG_CONFIG_HOME", home.path());
+        cmd
+    };
+
+    otot()
+        .args([
+            "wrap",
+            "--",
+            "echo",
+            "https://ci.example.com/job?token=s3cret",
+        ])
+        .assert()
+        .success();
+    otot()
+        .args(["history"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("token=REDACTED"))
+        .stdout(predicate::str::contains("s3cret").not());
+    otot()
+        .args([
+            "wrap",
+            "--open",
+            "--",
+            "echo",
+            "https://ci.example.com/?token=s3cret",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--allow-credentials"));
+
+    Ok(())
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn doctor_reports_why_history_is_unavailable() -> Result<(), Box<dyn std::error::Error>> {
+    let home = assert_fs::TempDir::new()?;
+    let data_home = home.path().join("data");
+    std::fs::write(&data_home, "")?;
+
+    cargo_bin_cmd!("otot")
+        .env("XDG_DATA_HOME", &data_home)
+        .env("XDG_CONFIG_HOME", home.path())
+        .arg("doctor")
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("History:        unavailable"))
+        .stdout(predicate::str::contains(
+            "Failed to create application directory",
+        ));
+    cargo_bin_cmd!("otot")
+        .env("XDG_DATA_HOME", home.path())
+        .env("XDG_CONFIG_HOME", home.path())
+        .arg("doctor")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("History:        ok, 0 URLs"));
+
+    Ok(())
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn locked_urls_are_kept_by_prune() -> Result<(), Box<dyn std::error::Error>> {
+    let home = assert_fs::TempDir::new()?;
+    let otot = || {
+        let mut cmd = cargo_bin_cmd!("otot");
+        cmd.env("XDG_DATA_HOME", home.path())
+            .env("XDG_CONFIG_HOME", home.path());
+        cmd
+    };
+
+    otot()
+        .args(["wrap", "--", "echo", "https://licenses.example.com/renew"])
+        .assert()
+        .success();
+    otot()
+        .args(["lock", "licenses", "renew"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Locked https://licenses.example.com/renew",
+        ));
+    otot()
+        .args(["prune", "--url", "licenses"])
+        .assert()
+        .success();
+    otot()
+        .arg("lock")
+        .assert()
+        .success()
+        .stdout("https://licenses.example.com/renew\n");
+
+    Ok(())
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn record_remembers_a_url_without_opening_it() -> Result<(), Box<dyn std::error::Error>> {
+    let home = assert_fs::TempDir::new()?;
+    let otot = || {
+        let mut cmd = cargo_bin_cmd!("otot");
+        cmd.env("XDG_DATA_HOME", home.path())
+            .env("XDG_CONFIG_HOME", home.path());
+        cmd
+    };
+
+    otot()
+        .args([
+            "record",
+            "https://ci.example.com/builds/1234",
+            "--ttl",
+            "7d",
+        ])
+        .assert()
+        .success()
+        .stdout("Recorded https://ci.example.com/builds/1234; it expires in 1w\n");
+    otot()
+        .args(["record", "ci/builds"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("not a history pattern"));
+    otot()
+        .args(["query", "ci", "1234"])
+        .assert()
+        .success()
+        .stdout("https://ci.example.com/builds/1234\n");
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn debug_flag_logs_only_the_chosen_subsystem() -> Result<(), Box<dyn std::error::Error>> {
+    let home = assert_fs::TempDir::new()?;
+    let otot = || {
+        let mut cmd = cargo_bin_cmd!("otot");
+        cmd.env("XDG_DATA_HOME", home.path())
+            .env("XDG_CONFIG_HOME", home.path())
+            .env_remove("RUST_LOG");
+        cmd
+    };
+
+    otot()
+        .args(["--debug", "browser", "misses"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Applying migration").not());
+    otot()
+        .args([
+            "--debug",
+            "database",
+            "record",
+            "https://github.com/rust-lang/rust",
+        ])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("DEBUG otot::database"));
+    otot()
+        .args(["config", "set", "-k", "log.levels.database", "-n", "debug"])
+        .assert()
+        .success();
+    otot()
+        .args(["misses"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("otot::database"));
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn db_stats_prints_totals_as_json() -> Result<(), Box<dyn std::error::Error>> {
+    let home = assert_fs::TempDir::new()?;
+    let otot = || {
+        let mut cmd = cargo_bin_cmd!("otot");
+        cmd.env("XDG_DATA_HOME", home.path())
+            .env("XDG_CONFIG_HOME", home.path());
+        cmd
+    };
+
+    for url in [
+        "https://github.com/rust-lang/rust",
+        "https://github.com/tokio-rs/tokio",
+        "https://docs.rs/serde",
+    ] {
+        otot().args(["record", url]).assert().success();
+    }
+    let output = otot().args(["db", "stats", "--json"]).output()?;
+    let stats: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+
+    assert_eq!(stats["urls"], 3);
+    assert_eq!(stats["domains"], 2);
+    assert_eq!(stats["total_score"], 3.0);
+    assert!(stats["newest_access"].as_i64().is_some());
+    otot()
+        .args(["db", "stats"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Domains:       2"));
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn invalid_utf8_arguments_are_replaced_with_a_warning() -> Result<(), Box<dyn std::error::Error>> {
+    use std::ffi::OsString;
+    use std::os::unix::ffi::OsStringExt;
+
+    let home = assert_fs::TempDir::new()?;
+    let mut cmd = cargo_bin_cmd!("otot");
+    cmd.env("XDG_DATA_HOME", home.path())
+        .env("XDG_CONFIG_HOME", home.path())
+        .arg("match")
+        .arg(OsString::from_vec(b"gh caf\xe9".to_vec()));
+
+    // Parsing goes on, and the pattern simply matches nothing
+    cmd.assert()
+        .code(1)
+        .stderr(predicate::str::contains("is not valid UTF-8"))
+        .stderr(predicate::str::contains("invalid UTF-8 was detected").not());
+
+    Ok(())
+}